@@ -1,10 +1,8 @@
 //! An anchor that sticks an object to a specific side or corner of the screen
 
 use sdl2::rect::{Point, Rect};
-use sdl2::render::Canvas;
-use sdl2::video::Window;
 
-use drawable::{DrawSettings, Drawable, KnownSize, Position, State};
+use drawable::{DrawSettings, DrawTarget, Drawable, KnownSize, Position, State};
 
 /// The direction to anchor the object to
 #[allow(missing_docs)]
@@ -56,11 +54,15 @@ impl<T: Drawable + KnownSize> Drawable for Anchor<T> {
         self.inner.step()
     }
 
+    fn step_back(&mut self) {
+        self.inner.step_back()
+    }
+
     fn state(&self) -> State {
         self.inner.state()
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, pos: &Position, settings: DrawSettings) {
+    fn draw(&self, canvas: &mut dyn DrawTarget, pos: &Position, settings: DrawSettings) {
         let rect = match pos {
             Position::Rect(r) => r,
             _ => {
@@ -0,0 +1,112 @@
+//! A content-addressed cache on disk, so that rendered LaTeX and dither maps don't have to be
+//! computed again every time the presentation starts.
+//!
+//! Everything is stored under a key, a hash of everything that affects the result. As long as
+//! nothing has changed, the same key gives the same result, so nothing in the cache has to be
+//! invalidated. Old results are never removed automatically, but the directory can safely be
+//! deleted at any time.
+
+extern crate fnv;
+
+use std::env::var_os;
+use std::fs::{create_dir_all, rename, File};
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Result as IResult, Write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use self::fnv::FnvHasher;
+
+/// Numbers the temporary files written by this process, so that threads writing the same entry
+/// don't write to the same file
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref CACHE_DIR: Mutex<Option<PathBuf>> = Mutex::new(default_cache_dir());
+}
+
+/// The default cache directory, `$XDG_CACHE_HOME/ytesrev` or `~/.cache/ytesrev`. `None` if
+/// neither `XDG_CACHE_HOME` nor `HOME` is set.
+pub fn default_cache_dir() -> Option<PathBuf> {
+    let mut dir = match var_os("XDG_CACHE_HOME") {
+        Some(cache) => PathBuf::from(cache),
+        None => {
+            let mut home = PathBuf::from(var_os("HOME")?);
+            home.push(".cache");
+            home
+        }
+    };
+    dir.push("ytesrev");
+    Some(dir)
+}
+
+/// Set the directory to cache results in. `None` disables the cache. This is set by the
+/// [`WindowManager`], see [`WindowManagerSettings::cache_dir`].
+///
+/// [`WindowManager`]: ../window/struct.WindowManager.html
+/// [`WindowManagerSettings::cache_dir`]: ../window/struct.WindowManagerSettings.html#structfield.cache_dir
+pub fn set_cache_dir(dir: Option<PathBuf>) {
+    if let Ok(mut cache_dir) = CACHE_DIR.lock() {
+        *cache_dir = dir;
+    }
+}
+
+/// The directory results are cached in, if the cache is enabled
+pub fn cache_dir() -> Option<PathBuf> {
+    CACHE_DIR.lock().ok().and_then(|dir| dir.clone())
+}
+
+/// Hash everything that affects a result into a key. The hash is stable between runs.
+///
+/// ```
+/// use ytesrev::diskcache::key;
+///
+/// assert_eq!(key(&("x^2", 250)), key(&("x^2", 250)));
+/// assert_ne!(key(&("x^2", 250)), key(&("x^3", 250)));
+/// ```
+pub fn key<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = FnvHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Read a cached result of some kind, such as `"latex"`. `None` if it isn't cached, or the cache
+/// is disabled.
+pub fn read(kind: &str, key: u64) -> Option<Vec<u8>> {
+    let path = entry_path(kind, key)?;
+
+    let mut data = Vec::new();
+    File::open(path).ok()?.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+/// Cache a result of some kind. Does nothing if the cache is disabled.
+///
+/// The result is first written to a temporary file, so a result that's only partially written is
+/// never read.
+pub fn write(kind: &str, key: u64, data: &[u8]) -> IResult<()> {
+    let path = match entry_path(kind, key) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+
+    let tmp_path = path.with_extension(format!(
+        "tmp{}-{}",
+        process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    File::create(&tmp_path)?.write_all(data)?;
+    rename(tmp_path, path)
+}
+
+fn entry_path(kind: &str, key: u64) -> Option<PathBuf> {
+    let mut path = cache_dir()?;
+    path.push(kind);
+    path.push(format!("{:016x}", key));
+    Some(path)
+}
@@ -1,21 +1,54 @@
 //! A dithering effect, useful for fading images and text, in and out.
 
 use std::cell::Cell;
+use std::hash::{Hash, Hasher};
+use std::mem::{discriminant, swap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
 use std::{f64, u64};
 
-use sdl2::pixels::PixelFormatEnum;
-use sdl2::render::{BlendMode, Canvas};
-use sdl2::video::Window;
+use super::rand::prng::XorShiftRng;
+use super::rand::{Rng, SeedableRng};
+use rayon::prelude::*;
 
-use super::rand::{thread_rng, Rng};
-
-use drawable::{DrawSettings, Drawable, KnownSize, Position, State};
+use diskcache;
+use drawable::cache::next_generation;
+use drawable::{DrawSettings, DrawTarget, Drawable, KnownSize, Position, State};
 use image::ImageContainer;
 
+/// How far through the dither map the default duration goes each second
 const DITHER_SPEED: f64 = 350.;
+/// The default edge softness, see [`Ditherer::edge_softness`]
+///
+/// [`Ditherer::edge_softness`]: struct.Ditherer.html#structfield.edge_softness
 const DITHER_ALPHA_SPEED: f64 = 140.;
+/// The longest default duration, in seconds
 const MAX_TIME: f64 = 2.5;
 
+/// What dither maps are stored as in the [`diskcache`]
+///
+/// [`diskcache`]: ../diskcache/index.html
+const DITHER_CACHE: &str = "dither-v3";
+
+static DEFAULT_SEED: AtomicU64 = AtomicU64::new(0);
+
+/// Set the seed used by ditherers that don't have a seed of their own, see [`Ditherer::seed`].
+/// This is set by the [`WindowManager`], see [`WindowManagerSettings::dither_seed`]. Default: 0
+///
+/// [`Ditherer::seed`]: struct.Ditherer.html#structfield.seed
+/// [`WindowManager`]: ../window/struct.WindowManager.html
+/// [`WindowManagerSettings::dither_seed`]: ../window/struct.WindowManagerSettings.html#structfield.dither_seed
+pub fn set_default_seed(seed: u64) {
+    DEFAULT_SEED.store(seed, Ordering::Relaxed);
+}
+
+/// The seed used by ditherers that don't have a seed of their own
+pub fn default_seed() -> u64 {
+    DEFAULT_SEED.load(Ordering::Relaxed)
+}
+
 #[derive(PartialEq, Copy, Clone)]
 enum DitherState {
     Nothing,
@@ -23,34 +56,202 @@ enum DitherState {
     DitherOut,
 }
 
+/// The size of the tile of blue noise used by [`DitherDirection::BlueNoise`]
+///
+/// [`DitherDirection::BlueNoise`]: enum.DitherDirection.html#variant.BlueNoise
+const BLUE_NOISE_SIZE: usize = 32;
+/// How far each step of an ordered pattern is in the dither map, so that patterns take about as
+/// long as sweeping across a few hundred pixels
+const BAYER_STEP: usize = 16;
+
+lazy_static! {
+    static ref BLUE_NOISE: Vec<usize> = blue_noise(BLUE_NOISE_SIZE);
+}
+
+/// A custom direction, see [`DitherDirection::Custom`]
+///
+/// [`DitherDirection::Custom`]: enum.DitherDirection.html#variant.Custom
+pub type DirectionFn = Arc<dyn Fn((usize, usize), (usize, usize)) -> usize + Send + Sync>;
+
 /// The direction to dither
-#[derive(PartialEq, Copy, Clone)]
+///
+/// Most directions are sweeps, which decide where the dithering starts, while the dither function
+/// decides how it spreads. The patterns, [`Bayer`] and [`BlueNoise`], decide when every pixel is
+/// dithered in by themselves, so the dither function isn't used with them.
+///
+/// ```
+/// use std::sync::Arc;
+/// use ytesrev::prelude::*;
+/// use ytesrev::ditherer::DitherDirection;
+///
+/// let ditherer = Ditherer::new(LatexObj::math("e^{i\\pi} = -1"))
+///     .with_direction(DitherDirection::Custom(Arc::new(|(x, y), _| x * y)));
+/// ```
+///
+/// [`Bayer`]: #variant.Bayer
+/// [`BlueNoise`]: #variant.BlueNoise
+#[derive(Clone)]
 #[allow(unused, missing_docs)]
 pub enum DitherDirection {
     Leftwards,
     Rightwards,
     Downwards,
     Upwards,
+    /// From the top left corner to the bottom right corner
+    DownRightwards,
+    /// From the top right corner to the bottom left corner
+    DownLeftwards,
+    /// From the bottom left corner to the top right corner
+    UpRightwards,
+    /// From the bottom right corner to the top left corner
+    UpLeftwards,
     Outwards,
+    /// From the edges to the centre
+    Inwards,
+    /// Around the centre, clockwise, starting at the top
+    Clockwise,
+    /// Around the centre, counterclockwise, starting at the top
+    Counterclockwise,
+    /// Outwards from a point, in pixels from the top left corner. The point may be outside the
+    /// object.
+    FromPoint(i64, i64),
+    /// An ordered dithering pattern, using an 8x8 Bayer matrix
+    Bayer,
+    /// A blue noise pattern, which looks random but without any clumps
+    BlueNoise,
+    /// Any other direction, given the position of the pixel and the size of the object. The
+    /// function is called for every pixel, from several threads. Custom directions are only equal,
+    /// and hash the same, if they share the same function.
+    ///
+    /// As the function is shared, `DitherDirection` is `Clone` but not `Copy`.
+    Custom(DirectionFn),
     None,
 }
 
+impl PartialEq for DitherDirection {
+    fn eq(&self, other: &DitherDirection) -> bool {
+        match (self, other) {
+            (DitherDirection::FromPoint(x1, y1), DitherDirection::FromPoint(x2, y2)) => {
+                (x1, y1) == (x2, y2)
+            }
+            (DitherDirection::Custom(f), DitherDirection::Custom(g)) => Arc::ptr_eq(f, g),
+            _ => discriminant(self) == discriminant(other),
+        }
+    }
+}
+
+impl Eq for DitherDirection {}
+
+impl Hash for DitherDirection {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        discriminant(self).hash(state);
+        match self {
+            DitherDirection::FromPoint(x, y) => (x, y).hash(state),
+            DitherDirection::Custom(f) => (Arc::as_ptr(f) as *const () as usize).hash(state),
+            _ => {}
+        }
+    }
+}
+
 impl DitherDirection {
     /// At what time should this pixel be faded in?
     pub fn value(&self, pos: (usize, usize), size: (usize, usize)) -> usize {
+        let distance = |(x, y): (f64, f64)| {
+            let dx = pos.0 as f64 - x;
+            let dy = pos.1 as f64 - y;
+            (dx * dx + dy * dy).sqrt()
+        };
+        let centre = (size.0 as f64 / 2., size.1 as f64 / 2.);
+
         match self {
             DitherDirection::Rightwards => pos.0,
             DitherDirection::Leftwards => size.0 - pos.0 + 1,
             DitherDirection::Downwards => pos.1,
             DitherDirection::Upwards => size.1 - pos.1 + 1,
-            DitherDirection::Outwards => {
-                let dx = pos.0 as f64 - size.0 as f64 / 2.;
-                let dy = pos.1 as f64 - size.1 as f64 / 2.;
-                (dx * dx + dy * dy).sqrt() as usize
+            DitherDirection::DownRightwards => pos.0 + pos.1,
+            DitherDirection::DownLeftwards => size.0 - pos.0 + 1 + pos.1,
+            DitherDirection::UpRightwards => pos.0 + size.1 - pos.1 + 1,
+            DitherDirection::UpLeftwards => size.0 - pos.0 + size.1 - pos.1 + 2,
+            DitherDirection::Outwards => distance(centre) as usize,
+            DitherDirection::Inwards => {
+                let furthest = (centre.0 * centre.0 + centre.1 * centre.1).sqrt();
+                (furthest - distance(centre)).max(0.) as usize + 1
+            }
+            DitherDirection::Clockwise | DitherDirection::Counterclockwise => {
+                let dx = pos.0 as f64 - centre.0;
+                let dy = pos.1 as f64 - centre.1;
+                // Screen coordinates point downwards, so this increases clockwise from the top
+                let mut angle = dx.atan2(-dy);
+                if angle < 0. {
+                    angle += 2. * f64::consts::PI;
+                }
+                if let DitherDirection::Counterclockwise = self {
+                    angle = 2. * f64::consts::PI - angle;
+                }
+                // Measured along the outermost circle, so it's roughly in pixels
+                (angle * centre.0.max(centre.1)) as usize
+            }
+            DitherDirection::FromPoint(x, y) => distance((*x as f64, *y as f64)) as usize,
+            DitherDirection::Bayer => bayer(pos) * BAYER_STEP,
+            DitherDirection::BlueNoise => {
+                let (x, y) = (pos.0 % BLUE_NOISE_SIZE, pos.1 % BLUE_NOISE_SIZE);
+                BLUE_NOISE[y * BLUE_NOISE_SIZE + x]
             }
+            DitherDirection::Custom(f) => f(pos, size),
             DitherDirection::None => 1,
         }
     }
+
+    /// Is this a pattern, deciding when every pixel is dithered in by itself?
+    pub fn is_pattern(&self) -> bool {
+        matches!(self, DitherDirection::Bayer | DitherDirection::BlueNoise)
+    }
+}
+
+/// The position of a pixel in the order of an 8x8 Bayer matrix, from 0 to 63
+fn bayer((x, y): (usize, usize)) -> usize {
+    // Interleave the bits of x xor y and y, with the lowest bits being the most significant
+    let xor = x ^ y;
+    let mut value = 0;
+    for bit in 0..3 {
+        value = value << 2 | ((xor >> bit) & 1) << 1 | ((y >> bit) & 1);
+    }
+    value
+}
+
+/// Generate a tile of blue noise, giving each pixel its position in the order. Each pixel is
+/// picked as far as possible from the ones picked before it, wrapping around the edges so that the
+/// tile can be repeated.
+fn blue_noise(size: usize) -> Vec<usize> {
+    let pixels = size * size;
+    let wrapped = |d: usize| d.min(size - d);
+
+    // How much a picked pixel crowds the pixels around it, by their offset
+    let crowding: Vec<f64> = (0..pixels)
+        .map(|i| {
+            let (dx, dy) = (wrapped(i % size), wrapped(i / size));
+            (-((dx * dx + dy * dy) as f64) / 4.5).exp()
+        })
+        .collect();
+
+    let mut crowded = vec![0.; pixels];
+    let mut order = vec![None; pixels];
+    for n in 0..pixels {
+        let picked = (0..pixels)
+            .filter(|&i| order[i].is_none())
+            .min_by(|&a, &b| crowded[a].partial_cmp(&crowded[b]).expect("No NaNs"))
+            .expect("Not all pixels are picked");
+        order[picked] = Some(n);
+
+        let (px, py) = (picked % size, picked / size);
+        for (i, crowded) in crowded.iter_mut().enumerate() {
+            let dx = (i % size + size - px) % size;
+            let dy = (i / size + size - py) % size;
+            *crowded += crowding[dy * size + dx];
+        }
+    }
+
+    order.into_iter().map(|n| n.unwrap_or(0)).collect()
 }
 
 /// A dithering function that is useful for images with transparency in them, fading regions with
@@ -100,23 +301,289 @@ pub fn color_dither_fn<T: ImageContainer + KnownSize>(image: &T, (x, y): (usize,
     grad_x.max(grad_y)
 }
 
+/// Everything a dither map is computed from
+struct DitherInput {
+    /// The gradient of the dither function, empty for patterns
+    grad: Vec<u64>,
+    /// The value of the direction for each pixel
+    order: Vec<u64>,
+    /// Whether the direction is a pattern, which is used as the dither map directly
+    pattern: bool,
+    /// The key the dither map is cached under
+    key: u64,
+}
+
+/// The dither maps of a ditherer, each with the time its last pixel starts dithering
+struct DitherMaps {
+    /// The size of the inner object the maps were computed for
+    size: (usize, usize),
+    /// The map used for dithering in
+    dither: (Vec<u64>, u64),
+    /// The map used for dithering out, if it's different
+    out_dither: Option<(Vec<u64>, u64)>,
+}
+
+/// A random number generator for a row in a pass of the dither map computation. As each row gets
+/// its own generator, the map doesn't depend on the order the rows are processed in. The generator
+/// and its seed are the same on every platform, so that the same seed always gives the same map.
+fn row_rng(seed: u64, pass: usize, row: usize) -> XorShiftRng {
+    let low = mix(seed ^ mix((pass as u64) << 32 | row as u64));
+    let high = mix(low.wrapping_add(0x9e37_79b9_7f4a_7c15));
+
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&low.to_le_bytes());
+    bytes[8..].copy_from_slice(&high.to_le_bytes());
+    XorShiftRng::from_seed(bytes)
+}
+
+/// Spread the bits of a number, so that similar numbers give very different results. This is the
+/// finalizer of SplitMix64.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The value of a direction for each pixel, see [`DitherDirection::value`]. Computed in parallel,
+/// which is why custom directions have to be thread safe.
+///
+/// [`DitherDirection::value`]: enum.DitherDirection.html#method.value
+fn direction_order(direction: &DitherDirection, (width, height): (usize, usize)) -> Vec<u64> {
+    (0..width * height)
+        .into_par_iter()
+        .map(|i| direction.value((i % width, i / width), (width, height)) as u64)
+        .collect()
+}
+
+/// Compute when each pixel should be dithered in, from the gradient computed by the dither
+/// function and the order of the direction. Returns the map together with the time the last pixel
+/// starts dithering in. The same seed always gives the same map.
+fn dither_map(
+    grad: &[u64],
+    data: &[u8],
+    (width, height): (usize, usize),
+    order: &[u64],
+    seed: u64,
+) -> (Vec<u64>, u64) {
+    // Rows are processed in parallel. Empty images have no rows, but a chunk size of zero isn't
+    // allowed.
+    let row_len = width.max(1);
+
+    // Select only local maximum
+
+    let mut dither = vec![0u64; width * height];
+
+    dither
+        .par_chunks_mut(row_len)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let mut rng = row_rng(seed, 0, y);
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let val = order[y * width + x].saturating_add(rng.gen_range(0, 100));
+
+                if x == 0 || x == width - 1 || y == 0 || y == height - 1 {
+                    *pixel = val;
+                    continue;
+                }
+                let i = y * width + x;
+                // Check left/right
+                if grad[i] > grad[i + 1] && grad[i] > grad[i - 1] && rng.gen() {
+                    *pixel = val;
+                    continue;
+                }
+                // Check up/down
+                if grad[i] > grad[i + width] && grad[i] > grad[i - width] && rng.gen() {
+                    *pixel = val;
+                    continue;
+                }
+            }
+        });
+
+    // Spread the selection
+    let mut dither_next = dither.clone();
+    for pass in 1..=50 {
+        dither_next.copy_from_slice(&dither);
+
+        {
+            let dither = &dither;
+            dither_next
+                .par_chunks_mut(row_len)
+                .enumerate()
+                .for_each(|(y, row)| {
+                    let mut rng = row_rng(seed, pass, y);
+                    for (x, pixel) in row.iter_mut().enumerate() {
+                        if *pixel != 0 {
+                            continue;
+                        }
+
+                        let mut around = [0u64; 12];
+                        let mut n_around = 0;
+                        for ry in y.saturating_sub(1)..(y + 3).min(height) {
+                            for rx in x.saturating_sub(1)..(x + 2).min(width) {
+                                let pxl = dither[ry * width + rx];
+                                if pxl > 0 {
+                                    around[n_around] = pxl;
+                                    n_around += 1;
+                                }
+                            }
+                        }
+                        if n_around == 0 || rng.gen() {
+                            continue;
+                        }
+
+                        // Sampled as u32, as the samples of usize differ between platforms
+                        let neighbour = rng.gen_range(0, n_around as u32) as usize;
+                        *pixel = around[neighbour].saturating_add(rng.gen_range(20, 40));
+                    }
+                });
+        }
+
+        swap(&mut dither, &mut dither_next);
+    }
+
+    let last = dither.par_iter().cloned().max().unwrap_or(0);
+
+    // Check for dead spots (areas that should be rendered but weren't)
+    dither.par_iter_mut().enumerate().for_each(|(i, pixel)| {
+        if data[i * 4 + 3] > 0 && *pixel == 0 {
+            *pixel = last;
+        }
+    });
+
+    (dither, last)
+}
+
+/// Store a dither map in the cache, as the time of the last pixel followed by each pixel, in
+/// little endian
+fn encode_dither(dither: &[u64], last: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 * (dither.len() + 1));
+    for value in Some(last).iter().chain(dither) {
+        for i in 0..8 {
+            data.push((value >> (8 * i)) as u8);
+        }
+    }
+    data
+}
+
+/// Read a dither map stored using `encode_dither`, if it has the right number of pixels
+fn decode_dither(data: &[u8], pixels: usize) -> Option<(Vec<u64>, u64)> {
+    if data.len() != 8 * (pixels + 1) {
+        return None;
+    }
+
+    let mut values = data.chunks(8).map(|bytes| {
+        bytes
+            .iter()
+            .enumerate()
+            .fold(0u64, |value, (i, &byte)| value | (byte as u64) << (8 * i))
+    });
+    let last = values.next()?;
+    Some((values.collect(), last))
+}
+
+/// Load a dither map from the cache, or compute and cache it if it isn't cached. Patterns are
+/// used as they are.
+fn load_dither_map(
+    input: &DitherInput,
+    data: &[u8],
+    (width, height): (usize, usize),
+    seed: u64,
+) -> (Vec<u64>, u64) {
+    if input.pattern {
+        let last = input.order.iter().cloned().max().unwrap_or(0);
+        return (input.order.clone(), last);
+    }
+
+    let cached = diskcache::read(DITHER_CACHE, input.key)
+        .and_then(|data| decode_dither(&data, width * height));
+    if let Some(cached) = cached {
+        return cached;
+    }
+
+    let (dither, last) = dither_map(&input.grad, data, (width, height), &input.order, seed);
+    if let Err(e) = diskcache::write(DITHER_CACHE, input.key, &encode_dither(&dither, last)) {
+        eprintln!("Couldn't cache dither map: {}", e);
+    }
+    (dither, last)
+}
+
+/// Load the dither maps for dithering in, and out if it's different
+fn load_dither_maps(
+    input: &DitherInput,
+    out_input: Option<&DitherInput>,
+    data: &[u8],
+    size: (usize, usize),
+    seed: u64,
+) -> DitherMaps {
+    DitherMaps {
+        size,
+        dither: load_dither_map(input, data, size, seed),
+        out_dither: out_input.map(|out_input| load_dither_map(out_input, data, size, seed)),
+    }
+}
+
+/// A function determining what regions to dither first, see [`alpha_dither_fn`] and
+/// [`color_dither_fn`]
+///
+/// [`alpha_dither_fn`]: fn.alpha_dither_fn.html
+/// [`color_dither_fn`]: fn.color_dither_fn.html
+pub type DitherFn<T> = Box<dyn Fn(&T, (usize, usize)) -> u64>;
+
 /// The ditherer itself. The inner type `T` is the thing to be dithered
 pub struct Ditherer<T: ImageContainer + 'static> {
     /// The inner object to be dithered
     pub inner: T,
-    /// The time each pixel will be dithered in
-    pub dither: Option<Vec<Vec<u64>>>,
-    max_time: u64,
+    /// The time each pixel will be dithered in, stored row by row
+    pub dither: Option<Vec<u64>>,
+    /// When the last pixel starts dithering in, in the dither map
+    last: u64,
+    /// The time each pixel will be dithered out, stored row by row, if it's different from
+    /// `dither`
+    pub out_dither: Option<Vec<u64>>,
+    /// When the last pixel starts dithering out, in `out_dither`
+    out_last: u64,
+    /// The size of the inner object the dither maps were computed for
+    map_size: (usize, usize),
     cached: Cell<Vec<u8>>,
+    /// The dither maps being computed on another thread, after the inner object changed size
+    pending: Option<Receiver<DitherMaps>>,
     /// How long the ditherer has been dithering in for
     pub dither_in_time: f64,
     /// How long the ditherer has been dithering out for
     pub dither_out_time: f64,
     /// The function to determine what regionso to dither first
-    pub dither_fn: Box<dyn Fn(&T, (usize, usize)) -> u64>,
+    pub dither_fn: DitherFn<T>,
     /// The direction to dither in
     pub direction: DitherDirection,
+    /// The function to determine what regions to dither out first, if it's different from
+    /// `dither_fn`
+    pub out_dither_fn: Option<DitherFn<T>>,
+    /// The direction to dither out in, if it's different from `direction`
+    pub out_direction: Option<DitherDirection>,
+    /// The seed for the randomness in the dither map. Ditherers with the same seed, image and
+    /// settings always dither the same way. `None` uses the default seed, see
+    /// [`set_default_seed`].
+    ///
+    /// [`set_default_seed`]: fn.set_default_seed.html
+    pub seed: Option<u64>,
+    /// How long dithering in takes, in seconds. `None` picks a duration based on the dither map,
+    /// roughly its size, of at most 2.5 seconds.
+    pub duration_in: Option<f64>,
+    /// How long dithering out takes, in seconds. `None` picks a duration like for `duration_in`.
+    pub duration_out: Option<f64>,
+    /// How soft the edge between dithered and not yet dithered pixels is. Each pixel fades over
+    /// this distance in the dither map, which is roughly in pixels along the direction. Default:
+    /// 140
+    pub edge_softness: f64,
+    /// Should the ditherer finish dithering instantly when the scene steps? See
+    /// [`Drawable::before_step`]
+    ///
+    /// [`Drawable::before_step`]: ../drawable/trait.Drawable.html#method.before_step
+    pub finish_on_step: bool,
     dithering: DitherState,
+    /// The state before each step, to be able to step back. Stepping back can't go past the
+    /// state the ditherer was created in.
+    history: Vec<DitherState>,
     dither_start: bool,
 }
 
@@ -151,14 +618,26 @@ impl<T: ImageContainer> Ditherer<T> {
         Ditherer {
             inner,
             dither,
-            max_time: 0,
+            last: 0,
+            out_dither: None,
+            out_last: 0,
+            map_size: (0, 0),
             cached: Cell::new(Vec::new()),
+            pending: None,
             dither_in_time: 0.,
             dither_out_time: 0.,
             dither_fn: Box::new(alpha_dither_fn),
             direction: DitherDirection::Rightwards,
+            out_dither_fn: None,
+            out_direction: None,
+            seed: None,
+            duration_in: None,
+            duration_out: None,
+            edge_softness: DITHER_ALPHA_SPEED,
+            finish_on_step: false,
             dithering: DitherState::Nothing,
-            dither_start: false
+            history: Vec::new(),
+            dither_start: false,
         }
     }
 
@@ -169,13 +648,25 @@ impl<T: ImageContainer> Ditherer<T> {
         Ditherer {
             inner,
             dither,
-            max_time: 0,
+            last: 0,
+            out_dither: None,
+            out_last: 0,
+            map_size: (0, 0),
             cached: Cell::new(Vec::new()),
+            pending: None,
             dither_in_time: 0.,
             dither_out_time: 0.,
             dither_fn: Box::new(alpha_dither_fn),
             direction: DitherDirection::Rightwards,
+            out_dither_fn: None,
+            out_direction: None,
+            seed: None,
+            duration_in: None,
+            duration_out: None,
+            edge_softness: DITHER_ALPHA_SPEED,
+            finish_on_step: false,
             dithering: DitherState::DitherIn,
+            history: Vec::new(),
             dither_start: true,
         }
     }
@@ -187,13 +678,25 @@ impl<T: ImageContainer> Ditherer<T> {
         Ditherer {
             inner,
             dither,
-            max_time: 0,
+            last: 0,
+            out_dither: None,
+            out_last: 0,
+            map_size: (0, 0),
             cached: Cell::new(Vec::new()),
+            pending: None,
             dither_in_time: 0.,
             dither_out_time: 0.,
             dither_fn: Box::new(alpha_dither_fn),
             direction: DitherDirection::Rightwards,
+            out_dither_fn: None,
+            out_direction: None,
+            seed: None,
+            duration_in: None,
+            duration_out: None,
+            edge_softness: DITHER_ALPHA_SPEED,
+            finish_on_step: false,
             dithering: DitherState::DitherIn,
+            history: Vec::new(),
             dither_start: false,
         }
     }
@@ -217,6 +720,103 @@ impl<T: ImageContainer> Ditherer<T> {
         }
     }
 
+    /// Convert a ditherer to use a specific dither function when dithering out, instead of the
+    /// one used when dithering in
+    pub fn with_out_dither_fn<F>(self, f: F) -> Ditherer<T>
+    where
+        F: Fn(&T, (usize, usize)) -> u64 + 'static,
+    {
+        Ditherer {
+            out_dither_fn: Some(Box::new(f)),
+            ..self
+        }
+    }
+
+    /// Convert a ditherer to use a specific direction when dithering out, instead of the one used
+    /// when dithering in
+    ///
+    /// ```
+    /// use ytesrev::prelude::*;
+    ///
+    /// let ditherer = Ditherer::new(LatexObj::text("Hello"))
+    ///     .with_direction(DitherDirection::Rightwards)
+    ///     .with_out_direction(DitherDirection::Upwards);
+    /// ```
+    pub fn with_out_direction(self, dir: DitherDirection) -> Ditherer<T> {
+        Ditherer {
+            out_direction: Some(dir),
+            ..self
+        }
+    }
+
+    /// Convert a ditherer to use a specific seed, instead of the default one
+    ///
+    /// ```
+    /// use ytesrev::prelude::*;
+    ///
+    /// let ditherer = Ditherer::new(LatexObj::math("x^2")).with_seed(42);
+    /// assert_eq!(ditherer.seed, Some(42));
+    /// ```
+    pub fn with_seed(self, seed: u64) -> Ditherer<T> {
+        Ditherer {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Convert a ditherer to take a specific time, in seconds, to dither both in and out
+    ///
+    /// ```
+    /// use ytesrev::prelude::*;
+    ///
+    /// let ditherer = Ditherer::new(LatexObj::math("x^2")).with_duration(0.5);
+    /// assert_eq!(ditherer.duration_in, Some(0.5));
+    /// assert_eq!(ditherer.duration_out, Some(0.5));
+    /// ```
+    pub fn with_duration(self, duration: f64) -> Ditherer<T> {
+        Ditherer {
+            duration_in: Some(duration),
+            duration_out: Some(duration),
+            ..self
+        }
+    }
+
+    /// Convert a ditherer to take a specific time, in seconds, to dither in
+    pub fn with_in_duration(self, duration: f64) -> Ditherer<T> {
+        Ditherer {
+            duration_in: Some(duration),
+            ..self
+        }
+    }
+
+    /// Convert a ditherer to take a specific time, in seconds, to dither out
+    pub fn with_out_duration(self, duration: f64) -> Ditherer<T> {
+        Ditherer {
+            duration_out: Some(duration),
+            ..self
+        }
+    }
+
+    /// Convert a ditherer to use a specific edge softness, see [`edge_softness`]. Zero gives hard
+    /// edges.
+    ///
+    /// [`edge_softness`]: #structfield.edge_softness
+    pub fn with_edge_softness(self, softness: f64) -> Ditherer<T> {
+        Ditherer {
+            edge_softness: softness,
+            ..self
+        }
+    }
+
+    /// Convert a ditherer to finish dithering instantly whenever the scene steps, instead of
+    /// continuing in the background
+    pub fn with_finish_on_step(self) -> Ditherer<T> {
+        Ditherer {
+            finish_on_step: true,
+            ..self
+        }
+    }
+
     /// Start dither in this object
     pub fn dither_in(&mut self) {
         self.dithering = DitherState::DitherIn;
@@ -227,128 +827,241 @@ impl<T: ImageContainer> Ditherer<T> {
         self.dithering = DitherState::DitherOut;
     }
 
-    /// Is the ditherer dithered in?
-    fn is_dithered_in(&self) -> bool {
-        self.dither_in_time * DITHER_SPEED > self.max_time as f64
+    /// When every pixel is fully dithered, in a dither map where the last pixel starts at `last`
+    fn end(&self, last: u64) -> f64 {
+        last as f64 + self.edge_softness.max(0.)
     }
 
-    /// Is the ditherer dithered out?
-    fn is_dithered_out(&self) -> bool {
-        self.dither_out_time * DITHER_SPEED > self.max_time as f64
+    /// When the last pixel starts dithering out, in the map used for dithering out
+    fn last_out(&self) -> u64 {
+        if self.out_dither.is_some() {
+            self.out_last
+        } else {
+            self.last
+        }
     }
-}
 
-impl<T: ImageContainer> Drawable for Ditherer<T> {
-    fn content(&self) -> Vec<&dyn Drawable> {
-        vec![&self.inner]
+    /// How long dithering in takes, in seconds
+    fn in_duration(&self) -> f64 {
+        self.duration_in
+            .unwrap_or_else(|| (self.end(self.last) / DITHER_SPEED).min(MAX_TIME))
     }
-    fn content_mut(&mut self) -> Vec<&mut dyn Drawable> {
-        vec![&mut self.inner]
+
+    /// How long dithering out takes, in seconds
+    fn out_duration(&self) -> f64 {
+        self.duration_out
+            .unwrap_or_else(|| (self.end(self.last_out()) / DITHER_SPEED).min(MAX_TIME))
     }
 
-    fn load(&mut self) {
-        self.inner.load();
+    /// How far into a dither map an animation of a certain duration has come
+    fn progress(&self, time: f64, duration: f64, last: u64) -> f64 {
+        if duration > 0. {
+            (time / duration).min(1.) * self.end(last)
+        } else {
+            self.end(last)
+        }
+    }
 
-        let mut grad = vec![vec![0u64; self.inner.width()]; self.inner.height()];
+    /// Find everything a dither map is computed from. The dither function doesn't have to be
+    /// thread safe, so its gradient is found on one thread.
+    fn dither_input(
+        &self,
+        dither_fn: &dyn Fn(&T, (usize, usize)) -> u64,
+        direction: &DitherDirection,
+        seed: u64,
+    ) -> DitherInput {
+        let (width, height) = (self.inner.width(), self.inner.height());
+        let order = direction_order(direction, (width, height));
+
+        if direction.is_pattern() {
+            return DitherInput {
+                grad: Vec::new(),
+                key: diskcache::key(&((width, height), &order)),
+                order,
+                pattern: true,
+            };
+        }
 
-        // Find gradient
-        for x in 0..self.inner.width() {
-            for y in 0..self.inner.height() {
-                grad[y][x] = (*self.dither_fn)(&self.inner, (x, y));
+        let mut grad = vec![0u64; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                grad[y * width + x] = dither_fn(&self.inner, (x, y));
             }
         }
 
-        // Select only local maximum
+        // Functions can't be hashed, but the gradient and the order are all the dither function
+        // and the direction contribute
+        let key = diskcache::key(&(self.inner.get_data(), (width, height), &grad, &order, seed));
+
+        DitherInput {
+            grad,
+            order,
+            pattern: false,
+            key,
+        }
+    }
 
-        let mut dither = vec![vec![0u64; self.inner.width()]; self.inner.height()];
+    /// Find everything the dither maps are computed from, and the seed. The input for dithering
+    /// out is only found if it differs from the one for dithering in.
+    fn map_inputs(&self) -> (DitherInput, Option<DitherInput>, u64) {
+        let seed = self.seed.unwrap_or_else(default_seed);
 
-        let mut rng = thread_rng();
+        let input = self.dither_input(&*self.dither_fn, &self.direction, seed);
 
-        for y in 0..self.inner.height() {
-            for x in 0..self.inner.width() {
-                let val = self
-                    .direction
-                    .value((x, y), (self.inner.width(), self.inner.height()))
-                    as u64;
-                let val = val as u64 + rng.gen_range(0, 100);
+        let mut out_input = None;
+        if self.out_dither_fn.is_some() || self.out_direction.is_some() {
+            let out_direction = self.out_direction.as_ref().unwrap_or(&self.direction);
+            let out_dither_fn = self.out_dither_fn.as_ref().unwrap_or(&self.dither_fn);
 
-                if x == 0 || x == self.inner.width() - 1 || y == 0 || y == self.inner.height() - 1 {
-                    dither[y][x] = val;
-                    continue;
-                }
-                // Check left/right
-                if grad[y][x] > grad[y][x + 1] && grad[y][x] > grad[y][x - 1] && rng.gen() {
-                    dither[y][x] = val;
-                    continue;
-                }
-                // Check up/down
-                if grad[y][x] > grad[y + 1][x] && grad[y][x] > grad[y - 1][x] && rng.gen() {
-                    dither[y][x] = val;
-                    continue;
-                }
+            let input_out = self.dither_input(&**out_dither_fn, out_direction, seed);
+            if input_out.key != input.key {
+                out_input = Some(input_out);
             }
         }
 
-        // Spread the selection
-        for _ in 0..50 {
-            let mut dither_next = dither.clone();
+        (input, out_input, seed)
+    }
 
-            for y in 0..self.inner.height() {
-                for x in 0..self.inner.width() {
-                    let mut around = vec![];
-                    for dy in -1..=2 {
-                        for dx in -1..=1 {
-                            let (ry, rx) = ((y as isize + dy) as usize, (x as isize + dx) as usize);
-                            if let Some(pxl) = dither.get(ry).and_then(|line| line.get(rx)) {
-                                if *pxl > 0 {
-                                    around.push(*pxl);
-                                }
-                            }
-                        }
-                    }
-                    if around.is_empty() || rng.gen() {
-                        continue;
-                    }
+    /// Compute the dither maps for the current content of the inner object
+    fn load_maps(&mut self) {
+        let (input, out_input, seed) = self.map_inputs();
+        let size = (self.inner.width(), self.inner.height());
+
+        let maps = load_dither_maps(
+            &input,
+            out_input.as_ref(),
+            self.inner.get_data(),
+            size,
+            seed,
+        );
+        self.set_maps(maps);
+    }
 
-                    let val = *rng.choose(around.as_slice()).unwrap() + rng.gen_range(20, 40);
+    /// Start computing the dither maps for the current content of the inner object on another
+    /// thread. Nothing is drawn until they're done, see [`poll_maps`].
+    ///
+    /// [`poll_maps`]: #method.poll_maps
+    fn load_maps_in_background(&mut self) {
+        // The dither function isn't thread safe, so the inputs are found here
+        let (input, out_input, seed) = self.map_inputs();
+        let size = (self.inner.width(), self.inner.height());
+        let data = self.inner.get_data().clone();
+
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let maps = load_dither_maps(&input, out_input.as_ref(), &data, size, seed);
+            // The ditherer might have been dropped
+            let _ = sender.send(maps);
+        });
+
+        self.dither = None;
+        self.out_dither = None;
+        self.pending = Some(receiver);
+    }
 
-                    if dither[y][x] == 0 {
-                        dither_next[y][x] = val;
-                        self.max_time = self.max_time.max(val + DITHER_ALPHA_SPEED as u64);
-                    }
-                }
+    /// Use the dither maps computed on another thread, if they're done. Returns whether new maps
+    /// are used.
+    fn poll_maps(&mut self) -> bool {
+        let result = match self.pending {
+            Some(ref receiver) => receiver.try_recv(),
+            None => return false,
+        };
+        match result {
+            Ok(maps) => {
+                self.pending = None;
+                self.set_maps(maps);
+                true
             }
+            Err(TryRecvError::Empty) => false,
+            Err(TryRecvError::Disconnected) => {
+                // Computing the maps failed, so the inner object stays undithered
+                self.pending = None;
+                false
+            }
+        }
+    }
+
+    /// Use new dither maps
+    fn set_maps(&mut self, maps: DitherMaps) {
+        let (dither, last) = maps.dither;
+        self.dither = Some(dither);
+        self.map_size = maps.size;
+        self.last = last;
 
-            dither = dither_next;
+        self.out_dither = None;
+        if let Some((out_dither, out_last)) = maps.out_dither {
+            self.out_dither = Some(out_dither);
+            self.out_last = out_last;
         }
 
-        // Check for dead spots (areas that should be rendered but weren't)
-        for x in 0..self.inner.width() {
-            for y in 0..self.inner.height() {
-                let alpha = self.inner.get_data()[(y * self.inner.width() + x) * 4 + 3];
-                if alpha > 0 && dither[y][x] == 0 {
-                    dither[y][x] = self.max_time;
-                }
+        self.cached = Cell::new(vec![0; self.inner.get_data().len()]);
+    }
+
+    /// Has the inner object changed size since the dither maps were computed?
+    fn maps_outdated(&self) -> bool {
+        self.dither.is_some() && self.map_size != (self.inner.width(), self.inner.height())
+    }
+
+    /// Is the ditherer dithered in?
+    fn is_dithered_in(&self) -> bool {
+        self.dither_in_time >= self.in_duration()
+    }
+
+    /// Is the ditherer dithered out?
+    fn is_dithered_out(&self) -> bool {
+        self.dither_out_time >= self.out_duration()
+    }
+
+    /// Finish dithering in or out instantly
+    fn finish_dithering(&mut self) {
+        match self.dithering {
+            DitherState::Nothing => {}
+            DitherState::DitherIn => {
+                self.dither_in_time = self.in_duration();
+            }
+            DitherState::DitherOut => {
+                self.dither_in_time = self.in_duration();
+                self.dither_out_time = self.out_duration();
             }
         }
+    }
+}
+
+impl<T: ImageContainer> Drawable for Ditherer<T> {
+    fn content(&self) -> Vec<&dyn Drawable> {
+        vec![&self.inner]
+    }
+    fn content_mut(&mut self) -> Vec<&mut dyn Drawable> {
+        vec![&mut self.inner]
+    }
+
+    fn load(&mut self) {
+        self.inner.load();
+        self.load_maps();
 
         if self.dither_start {
             self.dithering = DitherState::DitherIn;
-            self.dither_in_time = self.max_time as f64 * DITHER_SPEED;
+            self.dither_in_time = self.in_duration();
         }
-
-        self.dither = Some(dither);
-        self.cached = Cell::new(
-            self.inner
-                .get_data()
-                .clone()
-                .into_iter()
-                .map(|_| 0)
-                .collect::<Vec<u8>>(),
-        );
     }
 
     fn update(&mut self, dt: f64) {
+        self.inner.update(dt);
+
+        // The inner object can change size, such as a LatexObj that has been rendered in the
+        // background, and then dithers in from the start once its maps are done. Maps that were
+        // computed for a size it has changed from since are computed again.
+        let new_maps = self.poll_maps();
+        if self.maps_outdated() {
+            self.load_maps_in_background();
+        } else if new_maps && self.dithering == DitherState::DitherIn {
+            self.dither_in_time = if self.dither_start {
+                self.in_duration()
+            } else {
+                0.
+            };
+        }
+
         match self.dithering {
             DitherState::DitherIn => {
                 if !self.is_dithered_in() {
@@ -367,7 +1080,20 @@ impl<T: ImageContainer> Drawable for Ditherer<T> {
         }
     }
 
+    fn skip_animation(&mut self) {
+        self.inner.skip_animation();
+        self.finish_dithering();
+    }
+
+    fn before_step(&mut self) {
+        self.inner.before_step();
+        if self.finish_on_step {
+            self.finish_dithering();
+        }
+    }
+
     fn step(&mut self) {
+        self.history.push(self.dithering);
         match self.dithering {
             DitherState::Nothing => {
                 self.dither_in();
@@ -380,6 +1106,27 @@ impl<T: ImageContainer> Drawable for Ditherer<T> {
         }
     }
 
+    fn step_back(&mut self) {
+        let previous = match self.history.pop() {
+            Some(previous) => previous,
+            None => return,
+        };
+        match (self.dithering, previous) {
+            (DitherState::DitherIn, DitherState::Nothing) => {
+                self.dithering = DitherState::Nothing;
+                self.dither_in_time = 0.;
+            }
+            (DitherState::DitherOut, DitherState::DitherIn) => {
+                self.inner.step_back();
+                self.dithering = DitherState::DitherIn;
+                self.dither_in_time = self.in_duration();
+                self.dither_out_time = 0.;
+            }
+            // Stepping after dithering out does nothing, so neither does undoing it
+            _ => {}
+        }
+    }
+
     fn state(&self) -> State {
         match self.dithering {
             DitherState::Nothing => State::Working,
@@ -394,12 +1141,17 @@ impl<T: ImageContainer> Drawable for Ditherer<T> {
         }
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, pos: &Position, settings: DrawSettings) {
-        let mut t_mult = 1.;
-        if self.max_time as f64 > MAX_TIME * DITHER_SPEED {
-            t_mult = self.max_time as f64 / (MAX_TIME * DITHER_SPEED);
+    fn draw(&self, canvas: &mut dyn DrawTarget, pos: &Position, settings: DrawSettings) {
+        if self.pending.is_some() {
+            // Drawing the inner object as it is would show it fully, before it dithers in
+            return;
         }
 
+        let in_progress = self.progress(self.dither_in_time, self.in_duration(), self.last);
+        let out_progress =
+            self.progress(self.dither_out_time, self.out_duration(), self.last_out());
+        let softness = self.edge_softness.max(0.);
+
         match self.dithering {
             DitherState::Nothing if !settings.notes_view => {}
             DitherState::DitherIn if self.is_dithered_in() && !settings.notes_view => {
@@ -409,20 +1161,24 @@ impl<T: ImageContainer> Drawable for Ditherer<T> {
                 if self.is_dithered_out() && !settings.notes_view {
                     return;
                 }
+                if self.inner.width() == 0 || self.inner.height() == 0 {
+                    // Nothing to dither yet
+                    return;
+                }
                 if let Some(ref dither) = self.dither {
                     let mut cached = self.cached.take();
+                    let out_dither = self.out_dither.as_ref().unwrap_or(dither);
 
                     for y in 0..self.inner.height() {
                         for x in 0..self.inner.width() {
                             let mut mult = 1.;
 
-                            let diff_out = dither[y][x] as f64
-                                - (self.dither_out_time * DITHER_SPEED * t_mult);
-                            mult *= (diff_out / DITHER_ALPHA_SPEED + 1.).min(1.).max(0.);
+                            let diff_out =
+                                out_dither[y * self.inner.width() + x] as f64 - out_progress;
+                            mult *= (diff_out / softness + 1.).min(1.).max(0.);
 
-                            let diff_in =
-                                (self.dither_in_time * DITHER_SPEED * t_mult) - dither[y][x] as f64;
-                            mult *= (diff_in / DITHER_ALPHA_SPEED).min(1.).max(0.);
+                            let diff_in = in_progress - dither[y * self.inner.width() + x] as f64;
+                            mult *= (diff_in / softness).min(1.).max(0.);
 
                             let idx = (y * self.inner.width() + x) * 4;
 
@@ -459,27 +1215,22 @@ impl<T: ImageContainer> Drawable for Ditherer<T> {
                             }
                         }
                     }
-                    let creator = canvas.texture_creator();
-                    let mut texture = creator
-                        .create_texture_target(
-                            Some(PixelFormatEnum::ABGR8888),
-                            self.inner.width() as u32,
-                            self.inner.height() as u32,
-                        )
-                        .expect("Can't make texture");
-
-                    texture.set_blend_mode(BlendMode::Blend);
-
-                    texture
-                        .update(None, cached.as_slice(), 4 * self.inner.width())
-                        .expect("Can't update");
-
-                    self.cached.set(cached);
-
                     let rect = pos
                         .into_rect_with_size(self.inner.width() as u32, self.inner.height() as u32);
 
-                    canvas.copy(&texture, None, rect).expect("Can't copy");
+                    canvas
+                        .copy_rgba_cached(
+                            cached.as_ptr() as usize,
+                            // The image is recomputed every frame
+                            next_generation(),
+                            cached.as_slice(),
+                            self.inner.width(),
+                            self.inner.height(),
+                            rect,
+                        )
+                        .expect("Can't copy");
+
+                    self.cached.set(cached);
                 } else {
                     self.inner.draw(canvas, pos, settings);
                 }
@@ -487,3 +1238,140 @@ impl<T: ImageContainer> Drawable for Ditherer<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::PngImage;
+
+    #[test]
+    fn dither_maps_survive_the_cache() {
+        let dither = vec![0, 1, 255, 256, u64::MAX, 1 << 40];
+        let data = encode_dither(&dither, 1234);
+
+        assert_eq!(data.len(), 8 * (dither.len() + 1));
+        assert_eq!(decode_dither(&data, dither.len()), Some((dither, 1234)));
+    }
+
+    #[test]
+    fn cached_dither_maps_of_another_size_are_ignored() {
+        let data = encode_dither(&[1, 2, 3], 3);
+
+        assert_eq!(decode_dither(&data, 4), None);
+        assert_eq!(decode_dither(&data[1..], 3), None);
+    }
+
+    #[test]
+    fn step_back_undoes_every_step() {
+        let mut ditherer = Ditherer::new(PngImage::new(0, 0, Vec::new()));
+        let mut states = Vec::new();
+        for _ in 0..4 {
+            states.push(ditherer.dithering);
+            ditherer.step();
+        }
+        assert!(ditherer.dithering == DitherState::DitherOut);
+
+        while let Some(state) = states.pop() {
+            ditherer.step_back();
+            assert!(ditherer.dithering == state);
+        }
+        ditherer.step_back();
+        assert!(ditherer.dithering == DitherState::Nothing);
+    }
+
+    #[test]
+    fn row_generators_are_reproducible() {
+        let mut rng = row_rng(42, 3, 7);
+        let values: Vec<u32> = (0..3).map(|_| rng.gen()).collect();
+        assert_eq!(values, vec![4191197732, 367231954, 1044744023]);
+
+        assert_ne!(row_rng(42, 3, 8).gen::<u32>(), values[0]);
+        assert_ne!(row_rng(42, 4, 7).gen::<u32>(), values[0]);
+        assert_ne!(row_rng(43, 3, 7).gen::<u32>(), values[0]);
+    }
+
+    #[test]
+    fn sweeps_start_at_their_edge() {
+        let (pos, size) = ((3, 2), (10, 6));
+
+        assert_eq!(DitherDirection::Rightwards.value(pos, size), 3);
+        assert_eq!(DitherDirection::Leftwards.value(pos, size), 8);
+        assert_eq!(DitherDirection::Downwards.value(pos, size), 2);
+        assert_eq!(DitherDirection::Upwards.value(pos, size), 5);
+        assert_eq!(DitherDirection::DownRightwards.value(pos, size), 5);
+        assert_eq!(DitherDirection::DownLeftwards.value(pos, size), 10);
+        assert_eq!(DitherDirection::UpRightwards.value(pos, size), 8);
+        assert_eq!(DitherDirection::UpLeftwards.value(pos, size), 13);
+        assert_eq!(DitherDirection::None.value(pos, size), 1);
+    }
+
+    #[test]
+    fn radial_directions_measure_from_the_centre() {
+        let size = (10, 6);
+
+        assert_eq!(DitherDirection::Outwards.value((5, 3), size), 0);
+        assert_eq!(DitherDirection::Outwards.value((9, 3), size), 4);
+        assert_eq!(DitherDirection::Inwards.value((5, 3), size), 6);
+        assert_eq!(DitherDirection::Inwards.value((0, 0), size), 1);
+        assert_eq!(DitherDirection::FromPoint(-3, 4).value((0, 0), size), 5);
+    }
+
+    #[test]
+    fn angular_directions_go_around_from_the_top() {
+        let size = (10, 10);
+        let (top, right, bottom, left) = ((5, 0), (10, 5), (5, 10), (0, 5));
+
+        let clockwise = DitherDirection::Clockwise;
+        assert_eq!(clockwise.value(top, size), 0);
+        assert_eq!(clockwise.value(right, size), 7);
+        assert_eq!(clockwise.value(bottom, size), 15);
+        assert_eq!(clockwise.value(left, size), 23);
+
+        let counterclockwise = DitherDirection::Counterclockwise;
+        assert_eq!(counterclockwise.value(right, size), 23);
+        assert_eq!(counterclockwise.value(left, size), 7);
+    }
+
+    #[test]
+    fn custom_directions_are_equal_when_sharing_a_function() {
+        let custom = DitherDirection::Custom(Arc::new(|(x, y), _| x * y));
+
+        assert_eq!(custom.value((3, 2), (10, 6)), 6);
+        assert!(custom == custom.clone());
+        assert!(custom != DitherDirection::Custom(Arc::new(|(x, y), _| x * y)));
+        assert!(DitherDirection::FromPoint(1, 2) == DitherDirection::FromPoint(1, 2));
+        assert!(DitherDirection::FromPoint(1, 2) != DitherDirection::FromPoint(2, 1));
+        assert!(DitherDirection::Outwards != DitherDirection::Inwards);
+    }
+
+    #[test]
+    fn bayer_matches_the_matrix() {
+        let rows: Vec<Vec<usize>> = (0..2)
+            .map(|y| (0..8).map(|x| bayer((x, y))).collect())
+            .collect();
+        assert_eq!(rows[0], vec![0, 32, 8, 40, 2, 34, 10, 42]);
+        assert_eq!(rows[1], vec![48, 16, 56, 24, 50, 18, 58, 26]);
+
+        let mut values: Vec<usize> = (0..64).map(|i| bayer((i % 8, i / 8))).collect();
+        values.sort();
+        assert_eq!(values, (0..64).collect::<Vec<_>>());
+
+        assert_eq!(bayer((11, 13)), bayer((3, 5)));
+    }
+
+    #[test]
+    fn blue_noise_orders_every_pixel_once() {
+        let mut noise = blue_noise(8);
+        noise.sort();
+        assert_eq!(noise, (0..64).collect::<Vec<_>>());
+
+        let direction = DitherDirection::BlueNoise;
+        let size = (100, 100);
+        assert_eq!(
+            direction.value((3, 5), size),
+            direction.value((3 + BLUE_NOISE_SIZE, 5 + 2 * BLUE_NOISE_SIZE), size)
+        );
+        assert!(direction.is_pattern());
+        assert!(!DitherDirection::Outwards.is_pattern());
+    }
+}
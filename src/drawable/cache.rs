@@ -0,0 +1,301 @@
+//! Keeping textures between frames, so that images are only uploaded when they change
+//!
+//! Keeping textures requires the `texture-cache` feature, which is on by default. It enables the
+//! `unsafe_textures` feature of sdl2 and thereby removes the lifetime from its `Texture` type,
+//! also for other crates using sdl2 in the same build, whose textures then have to be destroyed
+//! by hand. With the default features turned off, a [`CachedCanvas`] uploads every image each
+//! frame, like a normal `Canvas`.
+//!
+//! [`CachedCanvas`]: struct.CachedCanvas.html
+
+extern crate sdl2;
+
+#[cfg(feature = "texture-cache")]
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
+
+use super::{DrawTarget, TextureTarget};
+
+static GENERATION: AtomicUsize = AtomicUsize::new(1);
+
+/// Get a generation that hasn't been returned before, to give to
+/// [`DrawTarget::copy_rgba_cached`] when an image is created or its data changes.
+///
+/// [`DrawTarget::copy_rgba_cached`]: ../trait.DrawTarget.html#method.copy_rgba_cached
+pub fn next_generation() -> u64 {
+    GENERATION.fetch_add(1, Ordering::Relaxed) as u64
+}
+
+/// A texture kept between frames, together with the generation of the data in it
+#[cfg(feature = "texture-cache")]
+struct CachedTexture {
+    texture: Texture,
+    width: usize,
+    height: usize,
+    generation: u64,
+    used: bool,
+}
+
+/// A canvas that keeps the textures of the images drawn to it between frames. Images drawn using
+/// [`DrawTarget::copy_rgba_cached`] are only uploaded the first time they're drawn, and again
+/// when their generation changes. Textures that weren't used during a frame are freed when the
+/// frame is presented, see [`present`]. Textures are only kept with the `texture-cache` feature,
+/// see the [module documentation].
+///
+/// The canvas can be used as a normal `Canvas` through `Deref`.
+///
+/// ```
+/// use ytesrev::drawable::cache::next_generation;
+/// use ytesrev::drawable::{offscreen_canvas, CachedCanvas, DrawTarget};
+/// use ytesrev::sdl2::rect::Rect;
+///
+/// let mut canvas = CachedCanvas::new(offscreen_canvas(4, 2).unwrap());
+/// let red = [255, 0, 0, 255];
+/// canvas
+///     .copy_rgba_cached(0, next_generation(), &red, 1, 1, Rect::new(0, 0, 4, 2))
+///     .unwrap();
+/// # if cfg!(feature = "texture-cache") {
+/// assert_eq!(canvas.cached_textures(), 1);
+///
+/// canvas.present();
+/// assert_eq!(canvas.cached_textures(), 1);
+/// canvas.present();
+/// assert_eq!(canvas.cached_textures(), 0);
+/// # }
+/// ```
+///
+/// [`DrawTarget::copy_rgba_cached`]: trait.DrawTarget.html#method.copy_rgba_cached
+/// [`present`]: #method.present
+/// [module documentation]: index.html
+pub struct CachedCanvas<T: TextureTarget> {
+    /// The canvas being drawn to
+    pub canvas: Canvas<T>,
+    #[cfg(feature = "texture-cache")]
+    textures: HashMap<usize, CachedTexture>,
+}
+
+impl<T: TextureTarget> CachedCanvas<T> {
+    /// Wrap a canvas, starting without any textures
+    pub fn new(canvas: Canvas<T>) -> CachedCanvas<T> {
+        CachedCanvas {
+            canvas,
+            #[cfg(feature = "texture-cache")]
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Show everything drawn since the last call on the screen, and free the textures that weren't
+    /// drawn since then
+    pub fn present(&mut self) {
+        self.canvas.present();
+        self.evict_unused();
+    }
+
+    /// Free the textures that weren't drawn since the last call to this or [`present`]. This
+    /// should be called after each frame for canvases that aren't presented, such as offscreen
+    /// canvases.
+    ///
+    /// [`present`]: #method.present
+    #[cfg(feature = "texture-cache")]
+    pub fn evict_unused(&mut self) {
+        let unused: Vec<usize> = self
+            .textures
+            .iter()
+            .filter(|(_, cached)| !cached.used)
+            .map(|(&id, _)| id)
+            .collect();
+
+        for id in unused {
+            if let Some(cached) = self.textures.remove(&id) {
+                // The canvas is still alive, so the texture hasn't been freed by SDL
+                unsafe { cached.texture.destroy() };
+            }
+        }
+
+        for cached in self.textures.values_mut() {
+            cached.used = false;
+        }
+    }
+
+    /// Free the textures that weren't drawn since the last call to this or [`present`]. This
+    /// should be called after each frame for canvases that aren't presented, such as offscreen
+    /// canvases.
+    ///
+    /// [`present`]: #method.present
+    #[cfg(not(feature = "texture-cache"))]
+    pub fn evict_unused(&mut self) {}
+
+    /// How many textures are currently kept
+    #[cfg(feature = "texture-cache")]
+    pub fn cached_textures(&self) -> usize {
+        self.textures.len()
+    }
+
+    /// How many textures are currently kept
+    #[cfg(not(feature = "texture-cache"))]
+    pub fn cached_textures(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(feature = "texture-cache")]
+impl<T: TextureTarget> Drop for CachedCanvas<T> {
+    fn drop(&mut self) {
+        // Textures have to be destroyed before the canvas that created them
+        for (_, cached) in self.textures.drain() {
+            unsafe { cached.texture.destroy() };
+        }
+    }
+}
+
+impl<T: TextureTarget> Deref for CachedCanvas<T> {
+    type Target = Canvas<T>;
+
+    fn deref(&self) -> &Canvas<T> {
+        &self.canvas
+    }
+}
+
+impl<T: TextureTarget> DerefMut for CachedCanvas<T> {
+    fn deref_mut(&mut self) -> &mut Canvas<T> {
+        &mut self.canvas
+    }
+}
+
+impl<T: TextureTarget> DrawTarget for CachedCanvas<T> {
+    fn output_size(&self) -> Result<(u32, u32), String> {
+        self.canvas.output_size()
+    }
+
+    fn set_viewport(&mut self, rect: Option<Rect>) {
+        self.canvas.set_viewport(rect)
+    }
+    fn viewport(&self) -> Rect {
+        self.canvas.viewport()
+    }
+    fn set_scale(&mut self, scale_x: f32, scale_y: f32) -> Result<(), String> {
+        self.canvas.set_scale(scale_x, scale_y)
+    }
+    fn scale(&self) -> (f32, f32) {
+        self.canvas.scale()
+    }
+
+    fn set_draw_color(&mut self, color: Color) {
+        self.canvas.set_draw_color(color)
+    }
+    fn draw_color(&self) -> Color {
+        self.canvas.draw_color()
+    }
+    fn set_blend_mode(&mut self, blend: BlendMode) {
+        self.canvas.set_blend_mode(blend)
+    }
+    fn blend_mode(&self) -> BlendMode {
+        self.canvas.blend_mode()
+    }
+
+    fn clear(&mut self) {
+        self.canvas.clear()
+    }
+    fn draw_point(&mut self, point: Point) -> Result<(), String> {
+        self.canvas.draw_point(point)
+    }
+    fn draw_line(&mut self, start: Point, end: Point) -> Result<(), String> {
+        self.canvas.draw_line(start, end)
+    }
+    fn draw_rect(&mut self, rect: Rect) -> Result<(), String> {
+        self.canvas.draw_rect(rect)
+    }
+    fn fill_rect(&mut self, rect: Rect) -> Result<(), String> {
+        self.canvas.fill_rect(rect)
+    }
+
+    fn copy_rgba(
+        &mut self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        dst: Rect,
+    ) -> Result<(), String> {
+        DrawTarget::copy_rgba(&mut self.canvas, data, width, height, dst)
+    }
+
+    #[cfg(feature = "texture-cache")]
+    fn copy_rgba_cached(
+        &mut self,
+        id: usize,
+        generation: u64,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        dst: Rect,
+    ) -> Result<(), String> {
+        let reusable = match self.textures.get(&id) {
+            Some(cached) => cached.width == width && cached.height == height,
+            None => false,
+        };
+        if !reusable {
+            if let Some(cached) = self.textures.remove(&id) {
+                unsafe { cached.texture.destroy() };
+            }
+            let creator = T::texture_creator(&self.canvas);
+            let texture = create_rgba_texture(&creator, width, height)?;
+            self.textures.insert(
+                id,
+                CachedTexture {
+                    texture,
+                    width,
+                    height,
+                    // Generations start at 1, so the new texture is always filled
+                    generation: 0,
+                    used: false,
+                },
+            );
+        }
+
+        let cached = self
+            .textures
+            .get_mut(&id)
+            .expect("Texture was just inserted");
+        if cached.generation != generation {
+            cached
+                .texture
+                .update(None, data, 4 * width)
+                .map_err(|e| e.to_string())?;
+            cached.generation = generation;
+        }
+        cached.used = true;
+
+        self.canvas.copy(&cached.texture, None, dst)
+    }
+}
+
+/// A texture created by a `TextureCreator`, which only borrows it without unsafe textures
+#[cfg(feature = "texture-cache")]
+pub(crate) type CreatedTexture<'c> = Texture;
+#[cfg(not(feature = "texture-cache"))]
+pub(crate) type CreatedTexture<'c> = Texture<'c>;
+
+/// Create a texture for an image stored in RGBA order, with alpha blending enabled
+pub(crate) fn create_rgba_texture<'c, C>(
+    creator: &'c TextureCreator<C>,
+    width: usize,
+    height: usize,
+) -> Result<CreatedTexture<'c>, String> {
+    let mut texture = creator
+        .create_texture_target(
+            // The pixels are stored in RGBA order, but using PixelFormatEnum::RGBA8888 gives
+            // the wrong image for some odd reason.
+            Some(PixelFormatEnum::ABGR8888),
+            width as u32,
+            height as u32,
+        )
+        .map_err(|e| e.to_string())?;
+
+    texture.set_blend_mode(BlendMode::Blend);
+    Ok(texture)
+}
@@ -3,10 +3,19 @@
 extern crate sdl2;
 
 use sdl2::event::Event;
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::{Point, Rect};
-use sdl2::render::Canvas;
+use sdl2::render::{BlendMode, Canvas, RenderTarget, TextureCreator};
+use sdl2::surface::Surface;
 use sdl2::video::Window;
 
+use notes::Note;
+
+pub mod cache;
+
+use self::cache::create_rgba_texture;
+pub use self::cache::CachedCanvas;
+
 /// Where to draw a specific object.
 #[allow(unused)]
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -28,12 +37,17 @@ pub struct DrawSettings {
     pub notes_view: bool,
     /// Color of the background in RGB order
     pub background_color: (u8, u8, u8),
+    /// The size of the virtual canvas scenes are drawn on. Everything is drawn in these units and
+    /// then scaled uniformly to fit the window, keeping the aspect ratio by adding black bars
+    /// around it if needed. `None` draws directly in pixels.
+    pub logical_size: Option<(u32, u32)>,
 }
 
 /// The default draw settings for the main window
 pub const DSETTINGS_MAIN: DrawSettings = DrawSettings {
     notes_view: false,
     background_color: (255, 248, 234),
+    logical_size: Some((1200, 800)),
 };
 
 /// The default draw settings for the notes window
@@ -175,6 +189,13 @@ pub trait Drawable: Send {
     /// method is what is called.
     fn step(&mut self);
 
+    /// Undo the last call to [`Drawable::step`], going back to how the object looked after the
+    /// step before it. Animations are not replayed. Objects containing other objects should keep
+    /// track of which of them were stepped, and only step those back.
+    ///
+    /// By default, this does nothing, which is fine for objects where stepping does nothing.
+    fn step_back(&mut self) {}
+
     /// When any event occurs
     fn event(&mut self, e: Event) {
         for x in self.content_mut() {
@@ -192,8 +213,37 @@ pub trait Drawable: Send {
         }
     }
 
+    /// Skip any ongoing animation, making the object look like it has finished animating
+    fn skip_animation(&mut self) {
+        for content in &mut self.content_mut() {
+            content.skip_animation();
+        }
+    }
+
+    /// Called on every object in a scene when the scene is about to step, before anything is
+    /// stepped, including objects that won't be stepped themselves. This is used to finish
+    /// animations that are still running, see [`Ditherer::finish_on_step`].
+    ///
+    /// [`Ditherer::finish_on_step`]: ../ditherer/struct.Ditherer.html#structfield.finish_on_step
+    fn before_step(&mut self) {
+        for content in &mut self.content_mut() {
+            content.before_step();
+        }
+    }
+
+    /// The speaker notes attached to this object and its content that should currently be shown,
+    /// see [`Notes`]
+    ///
+    /// [`Notes`]: ../notes/struct.Notes.html
+    fn notes(&self) -> Vec<&Note> {
+        self.content()
+            .into_iter()
+            .flat_map(|content| content.notes())
+            .collect()
+    }
+
     /// Draw everything
-    fn draw(&self, _canvas: &mut Canvas<Window>, _position: &Position, _settings: DrawSettings);
+    fn draw(&self, _canvas: &mut dyn DrawTarget, _position: &Position, _settings: DrawSettings);
 }
 
 /// An object that has a determined size, like an image, but not a solid that can fit any
@@ -205,3 +255,274 @@ pub trait KnownSize: Drawable {
     /// The height of the object
     fn height(&self) -> usize;
 }
+
+/// Something that can be drawn to, such as a window or an offscreen surface. This contains the
+/// subset of the `Canvas` methods that ytesrev uses, with the same signatures, so that the same
+/// [`Drawable`]s can be rendered both on screen and into memory.
+///
+/// It is implemented for `Canvas<Window>` and `Canvas<Surface>`, as well as [`CachedCanvas`] which
+/// keeps textures between frames.
+///
+/// [`CachedCanvas`]: cache/struct.CachedCanvas.html
+pub trait DrawTarget {
+    /// The size of the area being drawn to, in pixels
+    fn output_size(&self) -> Result<(u32, u32), String>;
+
+    /// Set the area being drawn to, in the current scale. `None` uses the entire target.
+    fn set_viewport(&mut self, rect: Option<Rect>);
+    /// Get the area being drawn to, in the current scale
+    fn viewport(&self) -> Rect;
+    /// Set the scale everything is drawn with
+    fn set_scale(&mut self, scale_x: f32, scale_y: f32) -> Result<(), String>;
+    /// Get the scale everything is drawn with
+    fn scale(&self) -> (f32, f32);
+
+    /// Set the color used for drawing operations
+    fn set_draw_color(&mut self, color: Color);
+    /// Get the color used for drawing operations
+    fn draw_color(&self) -> Color;
+    /// Set the blend mode used for drawing operations
+    fn set_blend_mode(&mut self, blend: BlendMode);
+    /// Get the blend mode used for drawing operations
+    fn blend_mode(&self) -> BlendMode;
+
+    /// Fill the entire target with the draw color
+    fn clear(&mut self);
+    /// Draw a single point
+    fn draw_point(&mut self, point: Point) -> Result<(), String>;
+    /// Draw a line between two points
+    fn draw_line(&mut self, start: Point, end: Point) -> Result<(), String>;
+    /// Draw the outline of a rectangle
+    fn draw_rect(&mut self, rect: Rect) -> Result<(), String>;
+    /// Fill a rectangle
+    fn fill_rect(&mut self, rect: Rect) -> Result<(), String>;
+
+    /// Copy an image, stored as `width * height` pixels in RGBA order, into `dst`. The image is
+    /// alpha blended and stretched to fit the rectangle.
+    fn copy_rgba(
+        &mut self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        dst: Rect,
+    ) -> Result<(), String>;
+
+    /// Like [`copy_rgba`], but the target may keep the texture between frames, such as a
+    /// [`CachedCanvas`]. `id` identifies the image, usually the address of its data, and the image
+    /// is only uploaded again if it's drawn with another `generation` than it was last drawn with.
+    /// The generation has to change whenever the data does, see [`next_generation`].
+    ///
+    /// [`copy_rgba`]: #tymethod.copy_rgba
+    /// [`CachedCanvas`]: cache/struct.CachedCanvas.html
+    /// [`next_generation`]: cache/fn.next_generation.html
+    fn copy_rgba_cached(
+        &mut self,
+        _id: usize,
+        _generation: u64,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        dst: Rect,
+    ) -> Result<(), String> {
+        self.copy_rgba(data, width, height, dst)
+    }
+}
+
+/// A render target that can create textures. This is needed because `Canvas::texture_creator`
+/// isn't generic over the target in sdl2.
+pub trait TextureTarget: RenderTarget + Sized {
+    /// Create a texture creator for the canvas
+    fn texture_creator(canvas: &Canvas<Self>) -> TextureCreator<Self::Context>;
+}
+
+impl TextureTarget for Window {
+    fn texture_creator(canvas: &Canvas<Window>) -> TextureCreator<Self::Context> {
+        canvas.texture_creator()
+    }
+}
+
+impl<'s> TextureTarget for Surface<'s> {
+    fn texture_creator(canvas: &Canvas<Surface<'s>>) -> TextureCreator<Self::Context> {
+        canvas.texture_creator()
+    }
+}
+
+impl<T: TextureTarget> DrawTarget for Canvas<T> {
+    fn output_size(&self) -> Result<(u32, u32), String> {
+        Canvas::output_size(self)
+    }
+
+    fn set_viewport(&mut self, rect: Option<Rect>) {
+        Canvas::set_viewport(self, rect)
+    }
+    fn viewport(&self) -> Rect {
+        Canvas::viewport(self)
+    }
+    fn set_scale(&mut self, scale_x: f32, scale_y: f32) -> Result<(), String> {
+        Canvas::set_scale(self, scale_x, scale_y)
+    }
+    fn scale(&self) -> (f32, f32) {
+        Canvas::scale(self)
+    }
+
+    fn set_draw_color(&mut self, color: Color) {
+        Canvas::set_draw_color(self, color)
+    }
+    fn draw_color(&self) -> Color {
+        Canvas::draw_color(self)
+    }
+    fn set_blend_mode(&mut self, blend: BlendMode) {
+        Canvas::set_blend_mode(self, blend)
+    }
+    fn blend_mode(&self) -> BlendMode {
+        Canvas::blend_mode(self)
+    }
+
+    fn clear(&mut self) {
+        Canvas::clear(self)
+    }
+    fn draw_point(&mut self, point: Point) -> Result<(), String> {
+        Canvas::draw_point(self, point)
+    }
+    fn draw_line(&mut self, start: Point, end: Point) -> Result<(), String> {
+        Canvas::draw_line(self, start, end)
+    }
+    fn draw_rect(&mut self, rect: Rect) -> Result<(), String> {
+        Canvas::draw_rect(self, rect)
+    }
+    fn fill_rect(&mut self, rect: Rect) -> Result<(), String> {
+        Canvas::fill_rect(self, rect)
+    }
+
+    fn copy_rgba(
+        &mut self,
+        data: &[u8],
+        width: usize,
+        height: usize,
+        dst: Rect,
+    ) -> Result<(), String> {
+        let creator = T::texture_creator(self);
+        let mut texture = create_rgba_texture(&creator, width, height)?;
+
+        let result = texture
+            .update(None, data, 4 * width)
+            .map_err(|e| e.to_string())
+            .and_then(|_| self.copy(&texture, None, dst));
+
+        // Unsafe textures aren't freed when dropped
+        #[cfg(feature = "texture-cache")]
+        unsafe {
+            texture.destroy()
+        };
+        result
+    }
+}
+
+/// Draw something scaled by a factor into a rectangle. Inside `f`, the top left corner of `rect`
+/// is at (0, 0), and everything drawn is scaled by `scale` and cut off outside of `rect`.
+pub fn draw_scaled<F>(canvas: &mut dyn DrawTarget, rect: Rect, scale: f64, f: F)
+where
+    F: FnOnce(&mut dyn DrawTarget),
+{
+    draw_stretched(canvas, rect, (scale, scale), f);
+}
+
+/// Like [`draw_scaled`], but with different scales horizontally and vertically
+///
+/// [`draw_scaled`]: fn.draw_scaled.html
+pub fn draw_stretched<F>(canvas: &mut dyn DrawTarget, rect: Rect, scale: (f64, f64), f: F)
+where
+    F: FnOnce(&mut dyn DrawTarget),
+{
+    let (old_scale_x, old_scale_y) = canvas.scale();
+    let old_viewport = canvas.viewport();
+
+    // The viewport is set in the new scale, so everything has to be divided by it
+    canvas
+        .set_scale(
+            (old_scale_x as f64 * scale.0) as f32,
+            (old_scale_y as f64 * scale.1) as f32,
+        )
+        .expect("Can't scale");
+    canvas.set_viewport(Some(Rect::new(
+        ((old_viewport.x() + rect.x()) as f64 / scale.0) as i32,
+        ((old_viewport.y() + rect.y()) as f64 / scale.1) as i32,
+        (rect.width() as f64 / scale.0).round() as u32,
+        (rect.height() as f64 / scale.1).round() as u32,
+    )));
+
+    f(canvas);
+
+    canvas
+        .set_scale(old_scale_x, old_scale_y)
+        .expect("Can't scale");
+
+    // Only set an explicit viewport if there was one, so that it follows the size of the window
+    let (width, height) = canvas.output_size().expect("Can't get size");
+    let full = Rect::new(
+        0,
+        0,
+        (width as f32 / old_scale_x) as u32,
+        (height as f32 / old_scale_y) as u32,
+    );
+    if old_viewport == full {
+        canvas.set_viewport(None);
+    } else {
+        canvas.set_viewport(Some(old_viewport));
+    }
+}
+
+/// Fit an area of a specific size inside a rectangle, scaling it uniformly to be as large as
+/// possible while keeping the aspect ratio, and centering it. Returns the scale and where the area
+/// ends up.
+///
+/// ```
+/// use ytesrev::drawable::fit_into;
+/// use ytesrev::sdl2::rect::Rect;
+///
+/// let (scale, rect) = fit_into((1920, 1080), Rect::new(0, 0, 960, 960));
+/// assert_eq!(scale, 0.5);
+/// assert_eq!(rect, Rect::new(0, 210, 960, 540));
+/// ```
+pub fn fit_into(size: (u32, u32), rect: Rect) -> (f64, Rect) {
+    let scale = (rect.width() as f64 / size.0 as f64).min(rect.height() as f64 / size.1 as f64);
+    let width = (size.0 as f64 * scale).round() as u32;
+    let height = (size.1 as f64 * scale).round() as u32;
+
+    (
+        scale,
+        Rect::new(
+            rect.x() + (rect.width() as i32 - width as i32) / 2,
+            rect.y() + (rect.height() as i32 - height as i32) / 2,
+            width,
+            height,
+        ),
+    )
+}
+
+/// Create an offscreen canvas of a specific size. Anything drawn to it stays in memory, and can
+/// be read back using [`PngImage::from_canvas`]. This doesn't need a window or a display.
+///
+/// [`PngImage::from_canvas`]: ../image/struct.PngImage.html#method.from_canvas
+///
+/// ```
+/// use ytesrev::drawable::{offscreen_canvas, Drawable, DSETTINGS_MAIN, Position};
+/// use ytesrev::image::PngImage;
+/// use ytesrev::sdl2::rect::Rect;
+/// use ytesrev::solid::Solid;
+///
+/// let mut canvas = offscreen_canvas(4, 2).unwrap();
+/// Solid::new_rgba(255, 0, 0, 255).draw(
+///     &mut canvas,
+///     &Position::Rect(Rect::new(0, 0, 4, 2)),
+///     DSETTINGS_MAIN,
+/// );
+///
+/// let image = PngImage::from_canvas(&canvas).unwrap();
+/// assert_eq!((image.width, image.height), (4, 2));
+/// assert_eq!(&image.data[0..4], &[255, 0, 0, 255]);
+/// ```
+pub fn offscreen_canvas(width: u32, height: u32) -> Result<Canvas<Surface<'static>>, String> {
+    let surface = Surface::new(width, height, PixelFormatEnum::ABGR8888)?;
+    Canvas::from_surface(surface)
+}
@@ -1,9 +1,6 @@
 //! The empty object
 
-use sdl2::render::Canvas;
-use sdl2::video::Window;
-
-use drawable::{DrawSettings, Drawable, Position, State};
+use drawable::{DrawSettings, DrawTarget, Drawable, Position, State};
 
 /// An object that contains nothing and doesn't display anything.
 ///
@@ -28,5 +25,5 @@ impl Drawable for Empty {
         State::Hidden
     }
 
-    fn draw(&self, _canvas: &mut Canvas<Window>, _position: &Position, _settings: DrawSettings) {}
+    fn draw(&self, _canvas: &mut dyn DrawTarget, _position: &Position, _settings: DrawSettings) {}
 }
@@ -12,18 +12,21 @@ use ytesrev::prelude::*;
 fn main() {
     add_prelude("\\usepackage{skull}");
 
-    let slist = SceneList::new(vec![
+    let mut wmng = WindowManager::init_window(make_scenes(), default_settings("Showcase"))
+        .with_preview(make_scenes());
+
+    wmng.start();
+}
+
+fn make_scenes() -> SceneList {
+    SceneList::new(vec![
         Box::new(make_first_scene()),
         Box::new(make_second_scene()),
         Box::new(make_third_scene()),
         Box::new(make_fourth_scene()),
         Box::new(make_fifth_scene()),
         Box::new(make_sixth_scene()),
-    ]);
-
-    let mut wmng = WindowManager::init_window(slist, default_settings("Showcase"));
-
-    wmng.start();
+    ])
 }
 
 fn make_first_scene() -> impl Scene {
@@ -34,7 +37,10 @@ fn make_first_scene() -> impl Scene {
         true,
         vec![
             Box::new(Ditherer::new(LatexObj::text("Thing 1"))),
-            Box::new(Ditherer::new(LatexObj::text("Thing 2"))),
+            Box::new(
+                Notes::new(Ditherer::new(LatexObj::text("Thing 2")))
+                    .with_note(1, Note::latex("Thing 2 comes after \\emph{thing 1}")),
+            ),
             Box::new(WithSize::new((0, 40), Empty)),
             Box::new(Ditherer::new(LatexObj::text("Thing 3 - a bit down"))),
             Box::new(Stack::new(
@@ -52,9 +58,12 @@ fn make_first_scene() -> impl Scene {
 }
 
 fn make_second_scene() -> impl Scene {
-    DrawableWrapper(Ditherer::dithering_in(LatexObj::text(include_str!(
-        "color.tex"
-    ))))
+    DrawableWrapper(
+        Notes::new(Ditherer::dithering_in(LatexObj::text(include_str!(
+            "color.tex"
+        ))))
+        .with_note(0, Note::text("Colors work too, using the xcolor package")),
+    )
 }
 
 fn make_third_scene() -> impl Scene {
@@ -175,11 +184,9 @@ fn make_fifth_scene() -> impl Scene {
     ))
 }
 
-use ytesrev::drawable::{DrawSettings, Position, State};
+use ytesrev::drawable::{DrawSettings, DrawTarget, Position, State};
 use ytesrev::sdl2::event::Event;
 use ytesrev::sdl2::pixels::Color;
-use ytesrev::sdl2::render::Canvas;
-use ytesrev::sdl2::video::Window;
 
 struct Line(bool, f64);
 
@@ -198,6 +205,9 @@ impl Drawable for Line {
     fn step(&mut self) {
         self.0 = false;
     }
+    fn step_back(&mut self) {
+        self.0 = true;
+    }
     fn state(&self) -> State {
         if self.0 {
             State::Final
@@ -212,7 +222,7 @@ impl Drawable for Line {
         self.1 += dt;
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, pos: &Position, _: DrawSettings) {
+    fn draw(&self, canvas: &mut dyn DrawTarget, pos: &Position, _: DrawSettings) {
         if self.0 {
             let cent = pos.into_rect_with_size(10, 10).center();
 
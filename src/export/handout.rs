@@ -0,0 +1,197 @@
+//! Export a presentation as a PDF handout. Every page is an exported frame, assembled into a
+//! document using `pdflatex`.
+
+extern crate tempfile;
+
+use std::fs::{copy, File};
+use std::io::{BufWriter, Error, Result as IResult, Write};
+use std::path::Path;
+use std::process::Command;
+
+use tempfile::tempdir;
+
+use super::{copy_note, frame_name, save_frame, walk_steps, ExportSettings, ESETTINGS_MAIN};
+use latex::backend::LatexEngine;
+use latex::render::{added_prelude, escape};
+use notes::Note;
+use scene::Scene;
+
+const HANDOUT_PRELUDE: &str = include_str!("handout_prelude.tex");
+const HANDOUT_POSTLUDE: &str = "\\end{document}";
+
+/// Which frames to put in the handout
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HandoutPages {
+    /// One page for every step
+    EveryStep,
+    /// One page for every slide, showing only the final step of it
+    EverySlide,
+}
+
+/// Settings for how a handout should be exported
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HandoutSettings {
+    /// How to export each frame
+    pub export: ExportSettings,
+    /// Which frames to make pages of
+    pub pages: HandoutPages,
+    /// Should the speaker notes be written under each page?
+    pub notes: bool,
+}
+
+/// The default handout settings, with one page for each step and speaker notes
+pub const HSETTINGS_MAIN: HandoutSettings = HandoutSettings {
+    export: ESETTINGS_MAIN,
+    pages: HandoutPages::EveryStep,
+    notes: true,
+};
+
+struct Page {
+    image: String,
+    slide: usize,
+    notes: Vec<Note>,
+}
+
+/// Export a scene as a PDF handout to the specified path. Returns the number of pages.
+///
+/// The scene has to be loaded before, see [`load_scene`].
+///
+/// [`load_scene`]: ../../window/fn.load_scene.html
+pub fn export_handout<T: Scene>(
+    scene: &mut T,
+    settings: &HandoutSettings,
+    path: &Path,
+) -> IResult<usize> {
+    let dir = tempdir()?;
+
+    let mut pages: Vec<Page> = Vec::new();
+
+    walk_steps(scene, &settings.export, |frame| {
+        save_frame(frame, dir.path())?;
+
+        let page = Page {
+            image: frame_name(frame.step),
+            slide: frame.slide,
+            notes: frame.notes.iter().map(copy_note).collect(),
+        };
+
+        if settings.pages == HandoutPages::EverySlide {
+            if let Some(last) = pages.last_mut() {
+                if last.slide == page.slide {
+                    last.image = page.image;
+                    for note in page.notes {
+                        let shown = last.notes.iter().any(|last_note| {
+                            last_note.text == note.text && last_note.is_latex == note.is_latex
+                        });
+                        if !shown {
+                            last.notes.push(note);
+                        }
+                    }
+                    return Ok(());
+                }
+            }
+        }
+
+        pages.push(page);
+        Ok(())
+    })?;
+
+    let tex_path = dir.path().join("handout.tex");
+    create_tex(&tex_path, &pages, settings.notes)?;
+
+    let out = Command::new("pdflatex")
+        .current_dir(dir.path())
+        .arg("-interaction=nonstopmode")
+        .arg("handout.tex")
+        .output()?;
+
+    if !out.status.success() {
+        return Err(Error::other(format!(
+            "pdflatex failed:\n{}",
+            String::from_utf8_lossy(&out.stdout)
+        )));
+    }
+
+    copy(dir.path().join("handout.pdf"), path)?;
+
+    Ok(pages.len())
+}
+
+fn create_tex(tex_path: &Path, pages: &[Page], notes: bool) -> IResult<()> {
+    let mut tex_file = BufWriter::new(File::create(tex_path)?);
+
+    let (orientation, height) = if notes {
+        ("portrait", 0.45)
+    } else {
+        ("landscape", 1.)
+    };
+
+    // LaTeX in the notes can use anything added to the prelude of the presentation
+    writeln!(
+        tex_file,
+        "{}",
+        HANDOUT_PRELUDE
+            .replace("$ORIENTATION", orientation)
+            .replace("$PRELUDE", &added_prelude(LatexEngine::PdfLatex))
+    )?;
+
+    for page in pages {
+        writeln!(tex_file, "\\begin{{center}}")?;
+        writeln!(
+            tex_file,
+            "\\includegraphics[width=\\linewidth,height={}\\textheight,keepaspectratio]{{{}}}",
+            height, page.image
+        )?;
+        writeln!(tex_file, "\\end{{center}}")?;
+
+        if notes {
+            for note in &page.notes {
+                if note.is_latex {
+                    writeln!(tex_file, "{}\n", note.text)?;
+                    continue;
+                }
+
+                // Plain text is shown as written, with its line breaks
+                for paragraph in note.text.split("\n\n") {
+                    let lines = paragraph
+                        .lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .map(escape)
+                        .collect::<Vec<_>>()
+                        .join("\\newline\n");
+                    writeln!(tex_file, "{}\n", lines)?;
+                }
+            }
+        }
+
+        writeln!(tex_file, "\\newpage")?;
+    }
+
+    writeln!(tex_file, "{}", HANDOUT_POSTLUDE)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::read_to_string;
+
+    #[test]
+    fn only_plain_notes_are_escaped() {
+        let dir = tempdir().unwrap();
+        let tex_path = dir.path().join("handout.tex");
+        let pages = [Page {
+            image: frame_name(0),
+            slide: 0,
+            notes: vec![Note::latex("$e^{i\\pi} = -1$"), Note::text("100% sure")],
+        }];
+
+        create_tex(&tex_path, &pages, true).unwrap();
+        let tex = read_to_string(&tex_path).unwrap();
+
+        assert!(tex.contains("$e^{i\\pi} = -1$\n"));
+        assert!(tex.contains("100\\% sure\n"));
+    }
+}
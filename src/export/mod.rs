@@ -0,0 +1,141 @@
+//! Export a presentation to files, without opening any windows
+
+pub mod handout;
+pub mod video;
+
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Error, Result as IResult};
+use std::path::Path;
+
+use drawable::{offscreen_canvas, CachedCanvas, DrawSettings, DSETTINGS_MAIN};
+use image::PngImage;
+use notes::Note;
+use scene::{Action, Scene};
+use window::{draw_scene, YEvent};
+
+/// Settings for how a presentation should be exported
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ExportSettings {
+    /// The size of each frame, in pixels
+    pub size: (u32, u32),
+    /// The `DrawSettings` to draw each frame with
+    pub draw_settings: DrawSettings,
+    /// The simulated time between each update, in seconds
+    pub dt: f64,
+    /// How long to simulate after each step, in seconds. This should be long enough for every
+    /// animation, such as a [`Ditherer`] fading in, to finish.
+    ///
+    /// [`Ditherer`]: ../ditherer/struct.Ditherer.html
+    pub settle_time: f64,
+    /// The maximum number of steps to export, in case the presentation never finishes
+    pub max_steps: usize,
+}
+
+/// The default export settings, the same size as the main window
+pub const ESETTINGS_MAIN: ExportSettings = ExportSettings {
+    size: (1200, 800),
+    draw_settings: DSETTINGS_MAIN,
+    dt: 1. / 60.,
+    settle_time: 3.,
+    max_steps: 1000,
+};
+
+/// A single exported frame, the final state of a step
+pub struct Frame {
+    /// The index of the step, starting from zero
+    pub step: usize,
+    /// The slide the step is in, see [`Scene::slide`]
+    ///
+    /// [`Scene::slide`]: ../scene/trait.Scene.html#method.slide
+    pub slide: usize,
+    /// The speaker notes for the step, without their rendered images, see [`Scene::notes`]
+    ///
+    /// [`Scene::notes`]: ../scene/trait.Scene.html#method.notes
+    pub notes: Vec<Note>,
+    /// What was drawn
+    pub image: PngImage,
+}
+
+/// Walk through a scene step by step, calling `f` with the final frame of each step. The first
+/// frame is the scene before it has been stepped at all.
+///
+/// The scene has to be loaded before, see [`load_scene`]. Returns the number of frames.
+///
+/// [`load_scene`]: ../window/fn.load_scene.html
+pub fn walk_steps<T, F>(scene: &mut T, settings: &ExportSettings, mut f: F) -> IResult<usize>
+where
+    T: Scene,
+    F: FnMut(&Frame) -> IResult<()>,
+{
+    let mut canvas =
+        CachedCanvas::new(offscreen_canvas(settings.size.0, settings.size.1).map_err(sdl_error)?);
+
+    let mut nframes = 0;
+    settle(scene, settings);
+
+    while scene.action() == Action::Continue && nframes < settings.max_steps {
+        draw_scene(scene, &mut canvas, settings.draw_settings);
+
+        f(&Frame {
+            step: nframes,
+            slide: scene.slide(),
+            notes: scene.notes().into_iter().map(copy_note).collect(),
+            image: PngImage::from_canvas(&canvas).map_err(sdl_error)?,
+        })?;
+        canvas.evict_unused();
+        nframes += 1;
+
+        scene.event(YEvent::Step);
+        settle(scene, settings);
+    }
+
+    Ok(nframes)
+}
+
+/// Export the final frame of every step in a scene as numbered PNG files, `step-0000.png`,
+/// `step-0001.png` and so on, in the specified directory. Returns the number of files written.
+///
+/// The scene has to be loaded before, see [`load_scene`].
+///
+/// [`load_scene`]: ../window/fn.load_scene.html
+pub fn export_steps<T: Scene>(
+    scene: &mut T,
+    settings: &ExportSettings,
+    dir: &Path,
+) -> IResult<usize> {
+    create_dir_all(dir)?;
+
+    walk_steps(scene, settings, |frame| save_frame(frame, dir))
+}
+
+/// The file name of a frame exported by [`export_steps`]
+pub fn frame_name(idx: usize) -> String {
+    format!("step-{:04}.png", idx)
+}
+
+fn save_frame(frame: &Frame, dir: &Path) -> IResult<()> {
+    let file = File::create(dir.join(frame_name(frame.step)))?;
+    frame.image.save_to_path(BufWriter::new(file))?;
+    Ok(())
+}
+
+/// Copy the content of a note, without its rendered image
+fn copy_note(note: &Note) -> Note {
+    if note.is_latex {
+        Note::latex(note.text.clone())
+    } else {
+        Note::text(note.text.clone())
+    }
+}
+
+fn settle<T: Scene>(scene: &mut T, settings: &ExportSettings) {
+    let mut time = 0.;
+    while time < settings.settle_time && scene.action() == Action::Continue {
+        scene.update(settings.dt);
+        time += settings.dt;
+    }
+}
+
+fn sdl_error(e: String) -> Error {
+    Error::other(e)
+}
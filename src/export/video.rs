@@ -0,0 +1,213 @@
+//! Record a presentation as a video, using a fixed timestep instead of the wall clock. The
+//! presentation is stepped according to a script of step times, and every frame is either saved
+//! as a PNG or piped to `ffmpeg`.
+
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Error, ErrorKind, Result as IResult, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+
+use sdl2::surface::Surface;
+
+use super::sdl_error;
+use drawable::{offscreen_canvas, CachedCanvas, DrawSettings, DSETTINGS_MAIN};
+use image::PngImage;
+use scene::{Action, Scene};
+use window::{draw_scene, YEvent};
+
+/// Where the recorded frames should go
+#[derive(Clone, PartialEq, Debug)]
+pub enum RecordOutput {
+    /// Save every frame as a numbered PNG, `frame-000000.png`, `frame-000001.png` and so on, in
+    /// this directory
+    Frames(PathBuf),
+    /// Pipe the raw RGBA frames to `ffmpeg`, encoding a video to this path. The format is
+    /// decided by `ffmpeg` from the file extension.
+    Ffmpeg(PathBuf),
+}
+
+/// Settings for how a presentation should be recorded
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecordSettings {
+    /// The size of each frame, in pixels
+    pub size: (u32, u32),
+    /// The `DrawSettings` to draw each frame with
+    pub draw_settings: DrawSettings,
+    /// Frames per second. Every frame advances the presentation by exactly `1 / fps` seconds.
+    pub fps: u32,
+    /// The times, in seconds from the start, at which the presentation should be stepped
+    pub steps: Vec<f64>,
+    /// The maximum length of the recording in seconds. The recording also ends when the
+    /// presentation does.
+    pub length: f64,
+    /// Where to put the frames
+    pub output: RecordOutput,
+}
+
+/// Create default settings, recording at 60 FPS in the size of the main window
+pub fn record_settings(output: RecordOutput, steps: Vec<f64>, length: f64) -> RecordSettings {
+    RecordSettings {
+        size: (1200, 800),
+        draw_settings: DSETTINGS_MAIN,
+        fps: 60,
+        steps,
+        length,
+        output,
+    }
+}
+
+enum Sink {
+    Frames(PathBuf),
+    Ffmpeg(Child),
+}
+
+/// Record a scene. Returns the number of frames recorded.
+///
+/// The scene has to be loaded before, see [`load_scene`].
+///
+/// [`load_scene`]: ../../window/fn.load_scene.html
+pub fn record<T: Scene>(scene: &mut T, settings: &RecordSettings) -> IResult<usize> {
+    record_with(scene, settings, |_| true)
+}
+
+/// Record a scene, calling `f` with the scene after every recorded frame, such as to show a
+/// preview. The recording is stopped early if `f` returns `false`. Returns the number of frames
+/// recorded, or an error if a step time isn't a number or the FPS is zero.
+pub fn record_with<T, F>(scene: &mut T, settings: &RecordSettings, f: F) -> IResult<usize>
+where
+    T: Scene,
+    F: FnMut(&T) -> bool,
+{
+    if settings.steps.iter().any(|step| step.is_nan()) {
+        return Err(Error::new(ErrorKind::InvalidInput, "Step time is NaN"));
+    }
+    if settings.fps == 0 {
+        return Err(Error::new(ErrorKind::InvalidInput, "FPS is zero"));
+    }
+
+    let mut canvas =
+        CachedCanvas::new(offscreen_canvas(settings.size.0, settings.size.1).map_err(sdl_error)?);
+    let mut sink = Sink::open(settings)?;
+
+    match record_frames(scene, settings, &mut canvas, &mut sink, f) {
+        Ok(nframes) => {
+            sink.finish()?;
+            Ok(nframes)
+        }
+        Err(e) => {
+            sink.abort();
+            Err(e)
+        }
+    }
+}
+
+/// Record every frame into a sink, returning the number of frames recorded
+fn record_frames<T, F>(
+    scene: &mut T,
+    settings: &RecordSettings,
+    canvas: &mut CachedCanvas<Surface<'static>>,
+    sink: &mut Sink,
+    mut f: F,
+) -> IResult<usize>
+where
+    T: Scene,
+    F: FnMut(&T) -> bool,
+{
+    let dt = 1. / settings.fps as f64;
+    let mut steps = settings.steps.clone();
+    steps.sort_by(f64::total_cmp);
+    let mut steps = steps.into_iter().peekable();
+
+    let mut nframes = 0;
+    while scene.action() == Action::Continue {
+        let time = nframes as f64 * dt;
+        if time >= settings.length {
+            break;
+        }
+
+        while steps.peek().map(|&step| step <= time).unwrap_or(false) {
+            steps.next();
+            scene.event(YEvent::Step);
+        }
+
+        draw_scene(scene, canvas, settings.draw_settings);
+        let frame = PngImage::from_canvas(canvas).map_err(sdl_error)?;
+        canvas.evict_unused();
+        sink.write(nframes, &frame)?;
+        nframes += 1;
+
+        if !f(scene) {
+            break;
+        }
+
+        scene.update(dt);
+    }
+
+    Ok(nframes)
+}
+
+impl Sink {
+    fn open(settings: &RecordSettings) -> IResult<Sink> {
+        match settings.output {
+            RecordOutput::Frames(ref dir) => {
+                create_dir_all(dir)?;
+                Ok(Sink::Frames(dir.clone()))
+            }
+            RecordOutput::Ffmpeg(ref path) => {
+                let child = Command::new("ffmpeg")
+                    .args(["-y", "-loglevel", "error"])
+                    .args(["-f", "rawvideo", "-pixel_format", "rgba"])
+                    .arg("-video_size")
+                    .arg(format!("{}x{}", settings.size.0, settings.size.1))
+                    .arg("-framerate")
+                    .arg(format!("{}", settings.fps))
+                    .args(["-i", "-", "-pix_fmt", "yuv420p"])
+                    .arg(path.as_os_str())
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .spawn()?;
+                Ok(Sink::Ffmpeg(child))
+            }
+        }
+    }
+
+    fn write(&mut self, idx: usize, frame: &PngImage) -> IResult<()> {
+        match self {
+            Sink::Frames(ref dir) => {
+                let file = File::create(dir.join(format!("frame-{:06}.png", idx)))?;
+                frame.save_to_path(BufWriter::new(file))?;
+                Ok(())
+            }
+            Sink::Ffmpeg(ref mut child) => child
+                .stdin
+                .as_mut()
+                .expect("ffmpeg has no stdin")
+                .write_all(&frame.data),
+        }
+    }
+
+    fn finish(self) -> IResult<()> {
+        match self {
+            Sink::Frames(_) => Ok(()),
+            Sink::Ffmpeg(mut child) => {
+                // Close stdin so ffmpeg knows that there are no more frames
+                child.stdin.take();
+
+                let status = child.wait()?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(Error::other(format!("ffmpeg failed: {}", status)))
+                }
+            }
+        }
+    }
+
+    /// Stop after an error, without leaving `ffmpeg` running. The video is left unfinished.
+    fn abort(self) {
+        if let Sink::Ffmpeg(mut child) = self {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
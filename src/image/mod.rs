@@ -4,14 +4,16 @@ extern crate png;
 extern crate sdl2;
 
 use sdl2::pixels::{Color, PixelFormatEnum};
-use sdl2::render::{BlendMode, Canvas};
-use sdl2::video::Window;
+use sdl2::render::{Canvas, RenderTarget};
 
-use self::png::{ColorType, Decoder, DecodingError};
+use self::png::{
+    BitDepth, ColorType, Decoder, DecodingError, Encoder, EncodingError, HasParameters,
+};
 
-use std::io::Read;
+use std::io::{Read, Write};
 
-use drawable::{DrawSettings, Drawable, KnownSize, Position, State};
+use drawable::cache::next_generation;
+use drawable::{DrawSettings, DrawTarget, Drawable, KnownSize, Position, State};
 
 /// A PNG image. Currently only supports RGB and RGBA color types
 #[derive(Clone)]
@@ -22,9 +24,34 @@ pub struct PngImage {
     pub height: usize,
     /// The data in the image, stored in chunks of 4 per pixel, containing the image in RGBA order
     pub data: Vec<u8>,
+    /// Changed whenever `data` is, see [`PngImage::generation`]
+    ///
+    /// [`PngImage::generation`]: #method.generation
+    generation: u64,
 }
 
 impl PngImage {
+    /// Create an image from data stored in RGBA order
+    pub fn new(width: usize, height: usize, data: Vec<u8>) -> PngImage {
+        PngImage {
+            width,
+            height,
+            data,
+            generation: next_generation(),
+        }
+    }
+
+    /// Identifies the current content of the image, so that it's only uploaded again when drawn
+    /// after it has changed, see [`DrawTarget::copy_rgba_cached`]. It changes when the data is
+    /// accessed through [`get_data_mut`], so changes to `data` have to be made through it to be
+    /// shown.
+    ///
+    /// [`DrawTarget::copy_rgba_cached`]: ../drawable/trait.DrawTarget.html#method.copy_rgba_cached
+    /// [`get_data_mut`]: trait.ImageContainer.html#tymethod.get_data_mut
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
     /// Load an image from a specified source.
     pub fn load_from_path<R: Read>(r: R) -> Result<Self, DecodingError> {
         PngImage::load_from_path_transform(r, |x| x)
@@ -64,11 +91,28 @@ impl PngImage {
             }
         }
 
-        Ok(PngImage {
-            width,
-            height,
-            data,
-        })
+        Ok(PngImage::new(width, height, data))
+    }
+
+    /// Save the image as a PNG to a specified destination. The counterpart of
+    /// [`PngImage::load_from_path`].
+    pub fn save_to_path<W: Write>(&self, w: W) -> Result<(), EncodingError> {
+        let mut encoder = Encoder::new(w, self.width as u32, self.height as u32);
+        encoder.set(ColorType::RGBA).set(BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.data)
+    }
+
+    /// Read back everything that has been drawn to a canvas, such as an [`offscreen_canvas`].
+    ///
+    /// [`offscreen_canvas`]: ../drawable/fn.offscreen_canvas.html
+    pub fn from_canvas<T: RenderTarget>(canvas: &Canvas<T>) -> Result<Self, String> {
+        let (width, height) = canvas.output_size()?;
+        // ABGR8888 is stored as RGBA in memory, see `DrawTarget::copy_rgba`
+        let data = canvas.read_pixels(None, PixelFormatEnum::ABGR8888)?;
+
+        Ok(PngImage::new(width as usize, height as usize, data))
     }
 }
 
@@ -80,27 +124,19 @@ impl Drawable for PngImage {
         vec![]
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, pos: &Position, _settings: DrawSettings) {
-        let creator = canvas.texture_creator();
-        let mut texture = creator
-            .create_texture_target(
-                // The pixels are stored in RGBA order in self.data, but using
-                // PixelFormatEnum::RGBA8888 gives the wrong image for some odd reason.
-                Some(PixelFormatEnum::ABGR8888),
-                self.width as u32,
-                self.height as u32,
-            )
-            .expect("Can't make texture");
-
-        texture.set_blend_mode(BlendMode::Blend);
-
-        texture
-            .update(None, self.data.as_slice(), 4 * self.width)
-            .expect("Can't update");
-
+    fn draw(&self, canvas: &mut dyn DrawTarget, pos: &Position, _settings: DrawSettings) {
         let rect = pos.into_rect_with_size_unbounded(self.width as u32, self.height as u32);
 
-        canvas.copy(&texture, None, rect).expect("Can't copy");
+        canvas
+            .copy_rgba_cached(
+                self.data.as_ptr() as usize,
+                self.generation,
+                self.data.as_slice(),
+                self.width,
+                self.height,
+                rect,
+            )
+            .expect("Can't copy");
     }
 
     fn step(&mut self) {}
@@ -141,6 +177,7 @@ impl ImageContainer for PngImage {
         &self.data
     }
     fn get_data_mut(&mut self) -> &mut Vec<u8> {
+        self.generation = next_generation();
         &mut self.data
     }
     fn into_data(self) -> Vec<u8> {
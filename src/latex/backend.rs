@@ -0,0 +1,249 @@
+//! The programs used to render LaTeX, see [`LatexBackend`]
+//!
+//! [`LatexBackend`]: struct.LatexBackend.html
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The default resolution the expressions are rendered in
+pub const DEFAULT_DPI: u32 = 250;
+
+/// A program compiling the LaTeX document into a PDF
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum LatexEngine {
+    /// `pdflatex`, the default
+    PdfLatex,
+    /// `xelatex`, with Unicode input and system fonts through `fontspec`
+    XeLatex,
+    /// `lualatex`, with Unicode input and system fonts through `fontspec`
+    LuaLatex,
+    /// `tectonic`, which downloads the packages it needs by itself
+    Tectonic,
+}
+
+impl LatexEngine {
+    /// The name of the program
+    pub fn program(&self) -> &'static str {
+        match self {
+            LatexEngine::PdfLatex => "pdflatex",
+            LatexEngine::XeLatex => "xelatex",
+            LatexEngine::LuaLatex => "lualatex",
+            LatexEngine::Tectonic => "tectonic",
+        }
+    }
+
+    /// The command compiling a document into a PDF next to it. Errors don't stop the compilation,
+    /// so all of them end up in the `.log`-file next to the document.
+    pub fn command(&self, tex_path: &Path, args: &[String]) -> Command {
+        let mut command = Command::new(self.program());
+        if let Some(dir) = tex_path.parent() {
+            command.current_dir(dir);
+        }
+        match self {
+            LatexEngine::Tectonic => {
+                command.arg("--keep-logs");
+            }
+            _ => {
+                command.arg("-interaction=nonstopmode");
+            }
+        }
+        command.args(args);
+        if let Some(file) = tex_path.file_name() {
+            command.arg(file);
+        }
+        command
+    }
+}
+
+/// A program turning each page of a PDF into a PNG
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Rasterizer {
+    /// `pdftoppm` from poppler, the default
+    Pdftoppm,
+    /// `gs`, Ghostscript
+    Ghostscript,
+    /// `mutool` from MuPDF
+    Mutool,
+}
+
+impl Rasterizer {
+    /// The name of the program
+    pub fn program(&self) -> &'static str {
+        match self {
+            Rasterizer::Pdftoppm => "pdftoppm",
+            Rasterizer::Ghostscript => "gs",
+            Rasterizer::Mutool => "mutool",
+        }
+    }
+
+    /// The command rendering every page of a PDF, naming the pages after `prefix`, see
+    /// [`page_path`]
+    ///
+    /// [`page_path`]: #method.page_path
+    pub fn command(&self, pdf_path: &Path, prefix: &Path, dpi: u32) -> Command {
+        let mut command = Command::new(self.program());
+        let output = format!("{}-%d.png", prefix.display());
+        match self {
+            Rasterizer::Pdftoppm => {
+                command
+                    .arg(pdf_path)
+                    .arg(prefix)
+                    .arg("-r")
+                    .arg(dpi.to_string())
+                    .arg("-png");
+            }
+            Rasterizer::Ghostscript => {
+                command
+                    .args(["-dSAFER", "-dBATCH", "-dNOPAUSE", "-dQUIET"])
+                    .args([
+                        "-sDEVICE=png16m",
+                        "-dTextAlphaBits=4",
+                        "-dGraphicsAlphaBits=4",
+                    ])
+                    .arg(format!("-r{}", dpi))
+                    .arg(format!("-sOutputFile={}", output))
+                    .arg(pdf_path);
+            }
+            Rasterizer::Mutool => {
+                command
+                    .arg("draw")
+                    .arg("-q")
+                    .args(["-c", "rgb"])
+                    .arg("-r")
+                    .arg(dpi.to_string())
+                    .arg("-o")
+                    .arg(output)
+                    .arg(pdf_path);
+            }
+        }
+        command
+    }
+
+    /// Where a page, counting from 1, ends up when there are `pages` pages in total. `pdftoppm`
+    /// pads the page numbers to the length of the last one, the others don't.
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    /// use ytesrev::latex::backend::Rasterizer;
+    ///
+    /// let prefix = Path::new("/tmp/res");
+    /// assert_eq!(
+    ///     Rasterizer::Pdftoppm.page_path(prefix, 7, 12),
+    ///     PathBuf::from("/tmp/res-07.png")
+    /// );
+    /// assert_eq!(
+    ///     Rasterizer::Mutool.page_path(prefix, 7, 12),
+    ///     PathBuf::from("/tmp/res-7.png")
+    /// );
+    /// ```
+    pub fn page_path(&self, prefix: &Path, page: usize, pages: usize) -> PathBuf {
+        let number = match self {
+            Rasterizer::Pdftoppm => {
+                let digits = pages.to_string().len();
+                format!("{:0width$}", page, width = digits)
+            }
+            Rasterizer::Ghostscript | Rasterizer::Mutool => page.to_string(),
+        };
+        PathBuf::from(format!("{}-{}.png", prefix.display(), number))
+    }
+}
+
+/// The programs used to render LaTeX, and how. Set using [`set_backend`], or the [`latex_backend`]
+/// of the `WindowManagerSettings`.
+///
+/// ```
+/// use ytesrev::latex::backend::{LatexBackend, LatexEngine, Rasterizer};
+///
+/// let backend = LatexBackend::new(LatexEngine::XeLatex)
+///     .with_engine_args(vec!["-shell-escape".to_string()])
+///     .with_rasterizer(Rasterizer::Mutool)
+///     .with_dpi(300);
+/// assert_eq!(backend.engine, LatexEngine::XeLatex);
+/// ```
+///
+/// [`set_backend`]: ../render/fn.set_backend.html
+/// [`latex_backend`]: ../../window/struct.WindowManagerSettings.html#structfield.latex_backend
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct LatexBackend {
+    /// The program compiling the document. Default: `pdflatex`
+    pub engine: LatexEngine,
+    /// Extra arguments given to the engine, before the document
+    pub engine_args: Vec<String>,
+    /// The program turning the compiled document into images. Default: `pdftoppm`
+    pub rasterizer: Rasterizer,
+    /// The resolution the expressions are rendered in. Default: 250
+    pub dpi: u32,
+}
+
+impl LatexBackend {
+    /// Create a backend using an engine, with the default rasterizer and resolution
+    pub fn new(engine: LatexEngine) -> LatexBackend {
+        LatexBackend {
+            engine,
+            engine_args: Vec::new(),
+            rasterizer: Rasterizer::Pdftoppm,
+            dpi: DEFAULT_DPI,
+        }
+    }
+
+    /// Give the engine extra arguments
+    pub fn with_engine_args(self, engine_args: Vec<String>) -> LatexBackend {
+        LatexBackend {
+            engine_args,
+            ..self
+        }
+    }
+
+    /// Use another rasterizer
+    pub fn with_rasterizer(self, rasterizer: Rasterizer) -> LatexBackend {
+        LatexBackend { rasterizer, ..self }
+    }
+
+    /// Render in another resolution
+    pub fn with_dpi(self, dpi: u32) -> LatexBackend {
+        LatexBackend { dpi, ..self }
+    }
+}
+
+impl Default for LatexBackend {
+    fn default() -> LatexBackend {
+        LatexBackend::new(LatexEngine::PdfLatex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pdftoppm_pads_page_numbers() {
+        let prefix = Path::new("/tmp/res");
+
+        assert_eq!(
+            Rasterizer::Pdftoppm.page_path(prefix, 1, 100),
+            PathBuf::from("/tmp/res-001.png")
+        );
+        assert_eq!(
+            Rasterizer::Pdftoppm.page_path(prefix, 12, 12),
+            PathBuf::from("/tmp/res-12.png")
+        );
+        assert_eq!(
+            Rasterizer::Pdftoppm.page_path(prefix, 3, 9),
+            PathBuf::from("/tmp/res-3.png")
+        );
+    }
+
+    #[test]
+    fn ghostscript_doesnt_pad_page_numbers() {
+        let prefix = Path::new("/tmp/res");
+
+        assert_eq!(
+            Rasterizer::Ghostscript.page_path(prefix, 7, 12),
+            PathBuf::from("/tmp/res-7.png")
+        );
+        assert_eq!(
+            Rasterizer::Ghostscript.page_path(prefix, 12, 12),
+            PathBuf::from("/tmp/res-12.png")
+        );
+    }
+}
@@ -2,21 +2,35 @@
 
 extern crate sdl2;
 
-use super::render::{read_image, register_equation, LatexIdx};
-use drawable::{DrawSettings, Drawable, KnownSize, Position, State};
+use std::borrow::Cow;
+
+use super::render::{
+    error_placeholder, is_rendered, read_image, register_equation, render_in_background,
+    LatexError, LatexIdx,
+};
+use drawable::{DrawSettings, DrawTarget, Drawable, KnownSize, Position, State};
 use image::{ImageContainer, PngImage};
-use sdl2::{pixels::Color, render::Canvas, video::Window};
 
 /// Represents a LaTeX expression. When loaded, this will create a PNG of the expression and load
 /// it.
+///
+/// Expressions created after the presentation has started are rendered in the background, and
+/// shown once they're done. Until then, nothing is drawn, and the object is empty.
+///
+/// Expressions that can't be rendered are replaced by the error message, in red.
 pub struct LatexObj {
     /// The rendered document
     pub inner: Option<PngImage>,
     id: Option<LatexIdx>,
     /// The LaTeX expression that will be rendered
-    pub expr: &'static str,
+    pub expr: Cow<'static, str>,
     /// A LaTeX expression can either be text or math, with math being surrounded by dollar sings
     pub is_text: bool,
+    /// Why the expression couldn't be rendered, if it couldn't, in which case the error is shown
+    /// instead. Rendering errors are also printed when rendering, by the [`WindowManager`].
+    ///
+    /// [`WindowManager`]: ../../window/struct.WindowManager.html
+    pub error: Option<LatexError>,
 }
 
 impl KnownSize for LatexObj {
@@ -36,12 +50,15 @@ impl KnownSize for LatexObj {
     }
 }
 
+/// The data of a `LatexObj` that hasn't been rendered yet
+static NO_DATA: Vec<u8> = Vec::new();
+
 impl ImageContainer for LatexObj {
     fn get_data(&self) -> &Vec<u8> {
         if let Some(ref inner) = self.inner {
             inner.get_data()
         } else {
-            panic!("Use of imagecontainer on unloaded LatexObj");
+            &NO_DATA
         }
     }
     fn get_data_mut(&mut self) -> &mut Vec<u8> {
@@ -55,13 +72,14 @@ impl ImageContainer for LatexObj {
         if let Some(inner) = self.inner {
             inner.into_data()
         } else {
-            panic!("Use of imagecontainer on unloaded LatexObj");
+            Vec::new()
         }
     }
 }
 
 impl LatexObj {
-    /// Create a LaTeX object containing math
+    /// Create a LaTeX object containing math. The expression can be either a `&'static str` or a
+    /// `String`, so expressions can be generated at runtime.
     ///
     /// ```
     /// use ytesrev::latex::LatexObj;
@@ -69,17 +87,23 @@ impl LatexObj {
     /// let e_mc2 = LatexObj::math("E = mc^2");
     /// assert!(!e_mc2.is_text);
     /// assert_eq!(e_mc2.expr, "E = mc^2");
+    ///
+    /// let squares: Vec<LatexObj> = (1..=30)
+    ///     .map(|n| LatexObj::math(format!("{}^2 = {}", n, n * n)))
+    ///     .collect();
+    /// assert_eq!(squares[2].expr, "3^2 = 9");
     /// ```
-    pub fn math(expr: &'static str) -> LatexObj {
+    pub fn math<S: Into<Cow<'static, str>>>(expr: S) -> LatexObj {
         LatexObj {
             inner: None,
             id: None,
-            expr,
+            expr: expr.into(),
             is_text: false,
+            error: None,
         }
     }
 
-    /// Create a LaTeX object containing text
+    /// Create a LaTeX object containing text, from either a `&'static str` or a `String`
     ///
     /// ```
     /// use ytesrev::latex::LatexObj;
@@ -88,12 +112,37 @@ impl LatexObj {
     /// assert!(lorem.is_text);
     /// assert_eq!(lorem.expr, "Lorem Ipsum");
     /// ```
-    pub fn text(expr: &'static str) -> LatexObj {
+    pub fn text<S: Into<Cow<'static, str>>>(expr: S) -> LatexObj {
         LatexObj {
             inner: None,
             id: None,
-            expr: expr,
+            expr: expr.into(),
             is_text: true,
+            error: None,
+        }
+    }
+
+    /// Take the rendered image
+    fn take_image(&mut self) {
+        if let Some(id) = self.id.take() {
+            let placeholder = error_placeholder(&id);
+            match read_image(id) {
+                Ok(image) => {
+                    self.inner = Some(image);
+                }
+                Err(e) => {
+                    // Show the error instead, unless it's the error that couldn't be rendered
+                    if self.error.is_none() {
+                        self.id = placeholder;
+                        self.error = Some(e);
+                        if self.id.as_ref().is_some_and(is_rendered) {
+                            self.take_image();
+                        } else if self.id.is_some() {
+                            render_in_background();
+                        }
+                    }
+                }
+            }
         }
     }
 }
@@ -114,35 +163,45 @@ impl Drawable for LatexObj {
         }
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, position: &Position, settings: DrawSettings) {
+    fn draw(&self, canvas: &mut dyn DrawTarget, position: &Position, settings: DrawSettings) {
+        // Nothing is drawn until the image is loaded, matching the size of 0x0
         if let Some(ref img) = self.inner {
             img.draw(canvas, position, settings);
-        } else {
-            canvas.set_draw_color(Color::RGB(255, 0, 255));
-            let rect = position.into_rect_with_size(100, 100);
-            canvas.fill_rect(rect).expect("Can't draw");
         }
     }
 
     fn register(&mut self) {
-        self.id = Some(register_equation(self.expr, self.is_text));
+        self.id = Some(register_equation(self.expr.clone(), self.is_text));
     }
 
     fn load(&mut self) {
-        if let Some(id) = self.id.take() {
-            match read_image(id) {
-                Ok(image) => {
-                    self.inner = Some(image);
-                }
-                Err(e) => {
-                    eprintln!(
-                        "Couldn't load image for expression `{}`: {:?}",
-                        self.expr, e
-                    );
-                }
+        if self.inner.is_some() {
+            return;
+        }
+        if self.id.is_none() {
+            if self.error.is_some() {
+                // Neither the expression nor the error could be rendered
+                return;
             }
+            // Created after all LaTeX was rendered
+            self.register();
+        }
+
+        if self.id.as_ref().is_some_and(is_rendered) {
+            self.take_image();
         } else {
-            eprintln!("Wrong loading order!");
+            render_in_background();
+        }
+    }
+
+    fn update(&mut self, _dt: f64) {
+        if self.inner.is_some() {
+            return;
+        }
+        match self.id {
+            Some(ref id) if is_rendered(id) => self.take_image(),
+            Some(_) => {}
+            None => self.load(),
         }
     }
 
@@ -1,4 +1,5 @@
 //! LaTeX rendering, using `pdflatex` and others.
+pub mod backend;
 pub mod latex_obj;
 pub mod render;
 
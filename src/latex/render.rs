@@ -6,33 +6,58 @@
 //!
 //! ## The rendereing process:
 //!
-//! 1. Collect all LaTeX expressions into a file, saved in /tmp/ytesrev/tmp.tex
-//! 2. Run `pdflatex` on the file
-//! 3. Run `pdfcrop` on to make all expressions the right size
-//! 4. Run `pdftoppm` on the resulting `.pdf`-files to generate `.png`-files of all the expressions
-//! 5. (Done for each `LatexObj`) Load the `.png`-file into a `PngImage`
+//! 0. Load the expressions that have been rendered before from the [`diskcache`]
+//! 1. Collect all other LaTeX expressions into a file, `tmp.tex`, in a new temporary directory
+//!    that is removed afterwards. Each expression is written twice, on a red and on a blue
+//!    background, on pages of their own.
+//! 2. Run the LaTeX engine, `pdflatex` by default, on the file. If it fails, smaller groups of
+//!    expressions are compiled until the broken ones are found, so that the rest can still be
+//!    rendered
+//! 3. Run the rasterizer, `pdftoppm` by default, on the resulting `.pdf`-file to generate a
+//!    `.png`-file of every page
+//! 4. Combine the red and blue version of each expression into a `PngImage` with transparency,
+//!    cropped to the expression, and save it in the cache
+//! 5. Render the error messages of the expressions that failed the same way, see
+//!    [`error_placeholder`]
+//! 6. (Done for each `LatexObj`) Take the loaded `PngImage`, or the error message
+//!
+//! The engine and rasterizer are chosen using [`set_backend`].
+//!
+//! Expressions registered after the presentation has started are rendered the same way by a
+//! worker thread in the background, see [`render_in_background`].
 //!
 //! [`LatexObj`]: ../latex_obj/struct.LatexObj.html
+//! [`diskcache`]: ../../diskcache/index.html
+//! [`error_placeholder`]: fn.error_placeholder.html
+//! [`render_in_background`]: fn.render_in_background.html
+//! [`set_backend`]: fn.set_backend.html
 
 extern crate sdl2;
 extern crate tempfile;
 
-use std::fs::{create_dir, remove_dir_all, File};
+use std::borrow::Cow;
+use std::error;
+use std::fmt;
+use std::fs::{read, File};
 use std::io::{Error, ErrorKind, Result as IResult, Write};
-use std::mem::drop;
+use std::mem::{drop, replace};
 use std::path::Path;
-use std::process::{exit, Command};
+use std::process::{Command, Output};
+use std::sync::mpsc::{channel, Sender};
 use std::sync::Mutex;
+use std::thread::spawn;
 use std::time::Instant;
 
+use super::backend::{LatexBackend, LatexEngine};
+use diskcache;
 use image::PngImage;
-use tempfile::tempdir;
+use tempfile::{Builder, TempDir};
 
 const LATEX_PRELUDE: &str = include_str!("latex_prelude.tex");
 const LATEX_POSTLUDE: &str = "\\end{document}";
 
 /// An error that might occur when rendering LaTeX expressions
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum LatexError {
     /// The specified LaTeX expression wasn't registered. This error should be impossible to get,
     /// as to get it you need an invalid index. See [`LatexIdx`]
@@ -41,6 +66,72 @@ pub enum LatexError {
     NotExisting,
     /// The LaTeX document hasn't been rendered yet. Run the [`render_all_equations`]
     NotLoaded,
+    /// LaTeX couldn't compile the document, because of the errors listed
+    Compile(Vec<CompileError>),
+    /// A program needed for rendering, such as `pdflatex` or `pdftoppm`, isn't installed, or not
+    /// in `PATH`
+    MissingTool(String),
+    /// A program other than LaTeX failed, with its output
+    ToolFailed {
+        /// The program that failed
+        tool: String,
+        /// What the program printed
+        output: String,
+    },
+    /// The files used while rendering couldn't be read or written
+    Io(String),
+}
+
+impl fmt::Display for LatexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LatexError::NotExisting => write!(f, "The expression was never registered"),
+            LatexError::NotLoaded => write!(f, "The expression hasn't been rendered yet"),
+            LatexError::Compile(errors) => {
+                write!(f, "LaTeX couldn't compile the document:")?;
+                for error in errors {
+                    write!(f, "\n    {}", error)?;
+                }
+                Ok(())
+            }
+            LatexError::MissingTool(tool) => {
+                write!(f, "`{}` wasn't found, is it installed and in PATH?", tool)
+            }
+            LatexError::ToolFailed { tool, output } => write!(f, "`{}` failed: {}", tool, output),
+            LatexError::Io(e) => write!(f, "Couldn't access the files used for rendering: {}", e),
+        }
+    }
+}
+
+impl error::Error for LatexError {}
+
+impl From<Error> for LatexError {
+    fn from(e: Error) -> LatexError {
+        LatexError::Io(e.to_string())
+    }
+}
+
+/// An error in the LaTeX document, found in the log of the engine
+#[derive(Debug, PartialEq, Clone)]
+pub struct CompileError {
+    /// The index of the expression the error is in, the same index as in the [`LatexIdx`] of the
+    /// expression. `None` if the error isn't in an expression, such as in the prelude.
+    ///
+    /// [`LatexIdx`]: struct.LatexIdx.html
+    pub index: Option<usize>,
+    /// The expression the error is in
+    pub expression: Option<String>,
+    /// The error message from LaTeX, such as `Undefined control sequence.`
+    pub message: String,
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.expression {
+            Some(ref expression) => write!(f, "`{}`: {}", expression, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
 }
 
 /// An index given to each [`LatexObj`], as they are all rendered in the same document
@@ -50,13 +141,45 @@ pub enum LatexError {
 /// [`LatexObj`]: ../latex_obj/struct.LatexObj.html
 pub struct LatexIdx(usize);
 
+/// How an expression is put into the document
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+enum Layout {
+    /// Math, surrounded by dollar signs
+    Math,
+    /// A single line of text
+    Text,
+    /// Paragraphs of text, wrapped at the width of the page. Plain text is escaped first.
+    Paragraph { plain: bool },
+}
+
+/// What has happened to the image of a registered expression
+enum RenderState {
+    /// The expression hasn't been rendered yet
+    Missing,
+    /// The expression has been rendered, but the image hasn't been read
+    Rendered(PngImage),
+    /// The expression couldn't be rendered. Math and text are replaced by the error message, a
+    /// paragraph registered with the index given here, see [`error_placeholder`].
+    ///
+    /// [`error_placeholder`]: fn.error_placeholder.html
+    Failed(LatexError, Option<usize>),
+    /// The image has been read using [`read_image`], so the expression is never rendered again
+    ///
+    /// [`read_image`]: fn.read_image.html
+    Taken,
+}
+
 lazy_static! {
-    static ref EQUATIONS: Mutex<Vec<(&'static str, bool, Option<PngImage>)>> =
+    static ref EQUATIONS: Mutex<Vec<(Cow<'static, str>, Layout, RenderState)>> =
         Mutex::new(Vec::new());
-    static ref PRELUDE: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+    static ref PRELUDE: Mutex<Vec<(Option<LatexEngine>, Cow<'static, str>)>> =
+        Mutex::new(Vec::new());
+    static ref BACKEND: Mutex<LatexBackend> = Mutex::new(LatexBackend::default());
+    static ref WORKER: Mutex<Sender<()>> = Mutex::new(spawn_worker());
 }
 
-/// Register an equation to be rendered. To render, use the [`render_all_equations`] method.
+/// Register an equation to be rendered. To render, use the [`render_all_equations`] method. The
+/// equation can be either a `&'static str` or a `String`, such as one generated at runtime.
 ///
 /// ```
 /// use ytesrev::latex::render::*;
@@ -69,17 +192,65 @@ lazy_static! {
 ///
 /// let valid_idx = register_equation("a^2 + b^2 = c+2", false);
 /// assert_eq!(read_image(valid_idx).err(), Some(LatexError::NotLoaded));
+///
+/// let generated_idx = register_equation(format!("x^{}", 3), false);
+/// assert_eq!(read_image(generated_idx).err(), Some(LatexError::NotLoaded));
+/// ```
+pub fn register_equation<S: Into<Cow<'static, str>>>(equation: S, is_text: bool) -> LatexIdx {
+    let layout = if is_text { Layout::Text } else { Layout::Math };
+    register(equation.into(), layout)
+}
+
+/// Register paragraphs of text to be rendered, wrapped at the width of the page. Plain text is
+/// escaped, so that it's shown as written, otherwise it's LaTeX in text mode. This is used for
+/// speaker notes, see [`Note`].
+///
 /// ```
-pub fn register_equation(equation: &'static str, is_text: bool) -> LatexIdx {
+/// use ytesrev::latex::render::*;
+///
+/// let idx = register_paragraph("Don't forget to mention 100% of the $ signs", true);
+/// assert_eq!(read_image(idx).err(), Some(LatexError::NotLoaded));
+/// ```
+///
+/// [`Note`]: ../../notes/struct.Note.html
+pub fn register_paragraph<S: Into<Cow<'static, str>>>(text: S, plain: bool) -> LatexIdx {
+    register(text.into(), Layout::Paragraph { plain })
+}
+
+fn register(equation: Cow<'static, str>, layout: Layout) -> LatexIdx {
     if let Ok(ref mut eqs) = EQUATIONS.lock() {
         let idx = eqs.len();
-        eqs.push((equation, is_text, None));
+        eqs.push((equation, layout, RenderState::Missing));
         LatexIdx(idx)
     } else {
         panic!("Can't eqs");
     }
 }
 
+/// Escape plain text so that it's shown as written in a LaTeX document
+///
+/// ```
+/// use ytesrev::latex::render::escape;
+///
+/// assert_eq!(escape("50% off"), "50\\% off");
+/// ```
+pub fn escape(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' => res.push_str("\\textbackslash{}"),
+            '~' => res.push_str("\\textasciitilde{}"),
+            '^' => res.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                res.push('\\');
+                res.push(ch);
+            }
+            _ => res.push(ch),
+        }
+    }
+    res
+}
+
 /// Add prelude to the LaTeX render.
 ///
 /// ```
@@ -90,21 +261,67 @@ pub fn register_equation(equation: &'static str, is_text: bool) -> LatexIdx {
 ///
 /// By default, amsmath is loaded, but nothing else.
 ///
-pub fn add_prelude(prelude: &'static str) {
+pub fn add_prelude<S: Into<Cow<'static, str>>>(prelude: S) {
     if let Ok(ref mut preludes) = PRELUDE.lock() {
-        preludes.push(prelude);
+        preludes.push((None, prelude.into()));
     }
     // TODO: Handle Mutex lock fail
 }
 
+/// Add prelude that's only used with a specific engine, such as fonts for `xelatex`
+///
+/// ```
+/// use ytesrev::latex::backend::LatexEngine;
+/// use ytesrev::latex::render::add_prelude_for;
+///
+/// add_prelude_for(LatexEngine::XeLatex, "\\usepackage{fontspec}");
+/// add_prelude_for(LatexEngine::XeLatex, "\\setmainfont{DejaVu Serif}");
+/// add_prelude_for(LatexEngine::PdfLatex, "\\usepackage[T1]{fontenc}");
+/// ```
+pub fn add_prelude_for<S: Into<Cow<'static, str>>>(engine: LatexEngine, prelude: S) {
+    if let Ok(ref mut preludes) = PRELUDE.lock() {
+        preludes.push((Some(engine), prelude.into()));
+    }
+}
+
+/// Choose the programs used to render LaTeX. This is set by the [`WindowManager`], see
+/// [`WindowManagerSettings::latex_backend`].
+///
+/// ```
+/// use ytesrev::latex::backend::{LatexBackend, LatexEngine};
+/// use ytesrev::latex::render::{backend, set_backend};
+///
+/// set_backend(LatexBackend::new(LatexEngine::XeLatex));
+/// assert_eq!(backend().engine, LatexEngine::XeLatex);
+/// ```
+///
+/// [`WindowManager`]: ../../window/struct.WindowManager.html
+/// [`WindowManagerSettings::latex_backend`]: ../../window/struct.WindowManagerSettings.html#structfield.latex_backend
+pub fn set_backend(backend: LatexBackend) {
+    if let Ok(mut current) = BACKEND.lock() {
+        *current = backend;
+    }
+}
+
+/// The programs used to render LaTeX
+pub fn backend() -> LatexBackend {
+    BACKEND
+        .lock()
+        .map(|backend| backend.clone())
+        .unwrap_or_default()
+}
+
 /// Reads an image from an LatexIdx.
 pub fn read_image(idx: LatexIdx) -> Result<PngImage, LatexError> {
     let res = if let Ok(ref mut eqs) = EQUATIONS.lock() {
         if let Some(ref mut x) = eqs.get_mut(idx.0) {
-            if x.2.is_some() {
-                Ok(x.2.take().unwrap())
-            } else {
-                Err(LatexError::NotLoaded)
+            match replace(&mut x.2, RenderState::Taken) {
+                RenderState::Rendered(image) => Ok(image),
+                RenderState::Failed(e, _) => Err(e),
+                state => {
+                    x.2 = state;
+                    Err(LatexError::NotLoaded)
+                }
             }
         } else {
             Err(LatexError::NotExisting)
@@ -116,27 +333,128 @@ pub fn read_image(idx: LatexIdx) -> Result<PngImage, LatexError> {
     res
 }
 
+/// Has the expression been rendered, or failed to render, so that [`read_image`] gives either
+/// its image or the error?
+///
+/// [`read_image`]: fn.read_image.html
+pub fn is_rendered(idx: &LatexIdx) -> bool {
+    if let Ok(eqs) = EQUATIONS.lock() {
+        match eqs.get(idx.0) {
+            Some(&(_, _, RenderState::Rendered(_))) | Some(&(_, _, RenderState::Failed(..))) => {
+                return true;
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// The error message shown instead of an expression that couldn't be rendered, in red. It's
+/// registered as soon as the expression fails, and rendered by the same call to
+/// [`render_all_equations`], so the message of an expression that failed at startup is ready when
+/// the presentation is loaded. Paragraphs, such as speaker notes, don't get one.
+///
+/// This has to be called before the error is taken using [`read_image`].
+///
+/// [`render_all_equations`]: fn.render_all_equations.html
+/// [`read_image`]: fn.read_image.html
+pub fn error_placeholder(idx: &LatexIdx) -> Option<LatexIdx> {
+    if let Ok(eqs) = EQUATIONS.lock() {
+        if let Some(&(_, _, RenderState::Failed(_, Some(placeholder)))) = eqs.get(idx.0) {
+            return Some(LatexIdx(placeholder));
+        }
+    }
+    None
+}
+
+/// Render all expressions that haven't been rendered yet on a worker thread, without blocking.
+/// Use [`is_rendered`] to check when an expression is done. This is used by [`LatexObj`]s created
+/// after the presentation has started.
+///
+/// Requests made while the worker is busy are rendered together once it's done.
+///
+/// [`is_rendered`]: fn.is_rendered.html
+/// [`LatexObj`]: ../latex_obj/struct.LatexObj.html
+pub fn render_in_background() {
+    if let Ok(worker) = WORKER.lock() {
+        if worker.send(()).is_err() {
+            eprintln!("The LaTeX worker has stopped");
+        }
+    }
+}
+
+fn spawn_worker() -> Sender<()> {
+    let (tx, rx) = channel();
+    spawn(move || {
+        while rx.recv().is_ok() {
+            // Everything registered so far is rendered at once, so the other requests are done
+            while rx.try_recv().is_ok() {}
+
+            if let Err(e) = render_all_equations() {
+                eprintln!("Couldn't render LaTeX: {}", e);
+            }
+        }
+    });
+    tx
+}
+
 /// Run the rendering process. This takes a few seconds.
 ///
 /// As with everything in this module, you probably don't want to do this yourself as this is
 /// automatically handled by the [`WindowManager`].
 ///
+/// If LaTeX can't compile the document, the broken expressions are found by compiling smaller and
+/// smaller groups of them, so that the rest can still be rendered. The broken expressions are
+/// marked as failed, and [`read_image`] gives their errors, which are also returned together. If
+/// the prelude is broken, or a program is missing, all expressions are marked as failed.
+///
 /// [`WindowManager`]: ../../window/struct.WindowManager.html
-pub fn render_all_equations() -> IResult<()> {
-    if let Ok(eqs) = EQUATIONS.lock() {
-        if eqs.len() == 0 {
-            return Ok(());
-        }
+/// [`read_image`]: fn.read_image.html
+pub fn render_all_equations() -> Result<(), LatexError> {
+    let backend = backend();
+    let setup = Setup {
+        prelude: document_prelude(backend.engine),
+        backend,
+    };
+    let missing = load_cached(&setup);
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let start = Instant::now();
+
+    let result = render_group(&setup, &missing);
+
+    // The error messages of the expressions that failed, registered when they were marked as
+    // failed. Their own errors aren't reported, the errors of the expressions already are.
+    let placeholders = load_cached(&setup);
+    if !placeholders.is_empty() {
+        let _ = render_group(&setup, &placeholders);
     }
-    let fallback = Path::new("/tmp/ytesrev").to_path_buf();
-    let path = tempdir().map(|x| x.into_path()).unwrap_or(fallback);
 
-    eprintln!("Rendering in {}", path.display());
+    let diff = Instant::now() - start;
+    eprintln!("Rendering took {:.2?}", diff);
+
+    result
+}
 
-    if path.exists() {
-        remove_dir_all(path.clone())?;
+/// Render a group of expressions, and mark the ones that couldn't be rendered as failed
+fn render_group(setup: &Setup, group: &[usize]) -> Result<(), LatexError> {
+    let result = match render_missing(setup, group) {
+        Err(LatexError::Compile(errors)) => isolate_broken(setup, group, errors),
+        result => result,
+    };
+    if let Err(ref e) = result {
+        mark_failed(group, e);
     }
-    create_dir(path.clone())?;
+    result
+}
+
+/// Render the expressions that weren't in the cache
+fn render_missing(setup: &Setup, missing: &[usize]) -> Result<(), LatexError> {
+    // The directory is removed when this is dropped
+    let dir = work_dir()?;
+    let path = dir.path().to_path_buf();
 
     let mut tex_path = path.clone();
     tex_path.push("tmp.tex");
@@ -147,45 +465,251 @@ pub fn render_all_equations() -> IResult<()> {
     let mut raw_path = path.clone();
     raw_path.push("tmp-res");
 
-    let start = Instant::now();
+    let starts = create_tex(&tex_path, &setup.prelude, missing)?;
+
+    render_tex(&tex_path, &pdf_path, &raw_path, &setup.backend, &starts)?;
 
-    create_tex(&tex_path)?;
+    read_pngs(&path, setup, missing)?;
 
-    render_tex(&tex_path, &pdf_path, &raw_path)?;
+    Ok(())
+}
 
-    read_pngs(&path)?;
+/// Create an empty directory to render in, which is removed when the returned guard is dropped
+fn work_dir() -> IResult<TempDir> {
+    Builder::new().prefix("ytesrev").tempdir()
+}
 
-    let diff = Instant::now() - start;
-    eprintln!("Rendering took {:.2?}", diff);
+/// Find the broken expressions in a group that couldn't be compiled together, so that the rest
+/// can still be rendered. The broken expressions are marked as failed, and their errors returned.
+fn isolate_broken(
+    setup: &Setup,
+    group: &[usize],
+    errors: Vec<CompileError>,
+) -> Result<(), LatexError> {
+    // Errors outside of the expressions might be in the prelude, which breaks every group
+    if errors.iter().all(|e| e.index.is_none()) {
+        check_prelude(setup)?;
+    }
+
+    let mut broken = Vec::new();
+    bisect(
+        group,
+        errors,
+        &mut |half| render_missing(setup, half),
+        &mut broken,
+    )?;
 
+    let mut errors = Vec::new();
+    for (idx, messages) in broken {
+        let expression = match EQUATIONS.lock() {
+            Ok(eqs) => Some(eqs[idx].0.to_string()),
+            Err(_) => None,
+        };
+        let own: Vec<CompileError> = messages
+            .into_iter()
+            .map(|message| CompileError {
+                index: Some(idx),
+                expression: expression.clone(),
+                message,
+            })
+            .collect();
+
+        mark_failed(&[idx], &LatexError::Compile(own.clone()));
+        errors.extend(own);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(LatexError::Compile(errors))
+    }
+}
+
+/// Split a group that couldn't be compiled in halves and render them separately, until the
+/// expressions that can't be compiled even by themselves are found. These are added to `broken`,
+/// together with the messages of their errors.
+fn bisect<F>(
+    group: &[usize],
+    errors: Vec<CompileError>,
+    render: &mut F,
+    broken: &mut Vec<(usize, Vec<String>)>,
+) -> Result<(), LatexError>
+where
+    F: FnMut(&[usize]) -> Result<(), LatexError>,
+{
+    if let [idx] = *group {
+        // All errors are caused by the only expression in the document
+        let mut messages = Vec::new();
+        for error in errors {
+            if !messages.contains(&error.message) {
+                messages.push(error.message);
+            }
+        }
+
+        broken.push((idx, messages));
+        return Ok(());
+    }
+
+    let (first, second) = group.split_at(group.len() / 2);
+    for half in &[first, second] {
+        match render(half) {
+            Err(LatexError::Compile(errors)) => bisect(half, errors, render, broken)?,
+            result => result?,
+        }
+    }
     Ok(())
 }
 
-fn create_tex(tex_path: &Path) -> IResult<()> {
-    let mut tex_file = File::create(tex_path)?;
+/// Compile a document without any expressions, to find errors in the prelude
+fn check_prelude(setup: &Setup) -> Result<(), LatexError> {
+    let dir = work_dir()?;
+    let tex_path = dir.path().join("tmp.tex");
+
+    create_tex(&tex_path, &setup.prelude, &[])?;
+    compile_tex(&tex_path, &setup.backend, &[])
+}
+
+/// Mark the expressions that haven't been rendered as failed, and register the error messages
+/// shown instead of them, see [`error_placeholder`]. The messages are registered while the lock
+/// is held, so that an expression is never seen as failed without its message.
+///
+/// [`error_placeholder`]: fn.error_placeholder.html
+fn mark_failed(indices: &[usize], error: &LatexError) {
+    if let Ok(ref mut eqs) = EQUATIONS.lock() {
+        for &idx in indices {
+            if let RenderState::Missing = eqs[idx].2 {
+                let placeholder = match eqs[idx].1 {
+                    Layout::Paragraph { .. } => None,
+                    Layout::Math | Layout::Text => {
+                        let text = placeholder_text(&eqs[idx].0, error);
+                        eqs.push((
+                            text.into(),
+                            Layout::Paragraph { plain: false },
+                            RenderState::Missing,
+                        ));
+                        Some(eqs.len() - 1)
+                    }
+                };
+                eqs[idx].2 = RenderState::Failed(error.clone(), placeholder);
+            }
+        }
+    }
+}
+
+/// The LaTeX shown instead of an expression that couldn't be rendered
+fn placeholder_text(expr: &str, error: &LatexError) -> String {
+    let message = match error {
+        LatexError::Compile(errors) => errors
+            .iter()
+            .map(|e| e.message.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        e => e.to_string(),
+    };
+
+    format!(
+        "\\textcolor{{red}}{{\\textbf{{LaTeX error:}} {}}}\n\n\\texttt{{{}}}",
+        escape(&message),
+        escape(expr)
+    )
+}
+
+/// Everything shared by the expressions rendered together
+struct Setup {
+    /// The start of the document, see `document_prelude`
+    prelude: String,
+    /// The programs used to render the document
+    backend: LatexBackend,
+}
+
+/// The start of the document, including everything added by [`add_prelude`], and by
+/// [`add_prelude_for`] for the engine used
+///
+/// [`add_prelude`]: fn.add_prelude.html
+/// [`add_prelude_for`]: fn.add_prelude_for.html
+fn document_prelude(engine: LatexEngine) -> String {
+    LATEX_PRELUDE.replace("$PRELUDE", &added_prelude(engine))
+}
+
+/// Everything added by [`add_prelude`], and by [`add_prelude_for`] for an engine
+///
+/// [`add_prelude`]: fn.add_prelude.html
+/// [`add_prelude_for`]: fn.add_prelude_for.html
+pub(crate) fn added_prelude(engine: LatexEngine) -> String {
     let mut added_prelude = String::new();
     if let Ok(prelude) = PRELUDE.lock() {
-        prelude.iter().for_each(|prelude| {
-            added_prelude.push_str(prelude);
-            added_prelude.push('\n');
-        });
+        prelude
+            .iter()
+            .filter(|(only_for, _)| only_for.is_none() || *only_for == Some(engine))
+            .for_each(|(_, prelude)| {
+                added_prelude.push_str(prelude);
+                added_prelude.push('\n');
+            });
     }
+    added_prelude
+}
 
-    writeln!(
-        tex_file,
-        "{}",
-        LATEX_PRELUDE.replace("$PRELUDE", &added_prelude)
-    )?;
+/// The key an expression is cached under. Everything that affects the rendered image is part of
+/// the key.
+fn cache_key(setup: &Setup, equation: &str, layout: Layout) -> u64 {
+    diskcache::key(&(&setup.prelude, equation, layout, &setup.backend))
+}
+
+/// Load all expressions that have been rendered before from the cache, returning the indices of
+/// the expressions that still have to be rendered
+fn load_cached(setup: &Setup) -> Vec<usize> {
+    let mut missing = Vec::new();
+
+    if let Ok(ref mut eqs) = EQUATIONS.lock() {
+        for (i, (equation, layout, ref mut im)) in eqs.iter_mut().enumerate() {
+            if !matches!(*im, RenderState::Missing) {
+                continue;
+            }
+
+            let key = cache_key(setup, equation, *layout);
+            let cached = diskcache::read("latex", key)
+                .and_then(|data| PngImage::load_from_path(data.as_slice()).ok());
+
+            match cached {
+                Some(image) => *im = RenderState::Rendered(image),
+                None => missing.push(i),
+            }
+        }
+    }
+
+    missing
+}
+
+/// Write the document, returning the line each expression starts on together with its index, to
+/// find which expressions errors are in
+fn create_tex(tex_path: &Path, prelude: &str, missing: &[usize]) -> IResult<Vec<(usize, usize)>> {
+    let mut tex_file = Vec::new();
+    let mut starts = Vec::new();
+
+    writeln!(tex_file, "{}", prelude)?;
 
     if let Ok(eqs) = EQUATIONS.lock() {
-        for equation in eqs.iter() {
+        for &idx in missing {
+            let equation = &eqs[idx];
+            let line = tex_file.iter().filter(|&&byte| byte == b'\n').count() + 1;
+            starts.push((line, idx));
+
             for col in &["red", "blue"] {
                 writeln!(tex_file, "\\begin{{equation*}}")?;
                 writeln!(tex_file, "\\colorbox{{{}}}{{\\makebox[\\linewidth]{{", col)?;
-                if equation.1 {
-                    writeln!(tex_file, "{}", equation.0)?;
-                } else {
-                    writeln!(tex_file, "$ {} $", equation.0)?;
+                match equation.1 {
+                    Layout::Math => writeln!(tex_file, "$ {} $", equation.0)?,
+                    Layout::Text => writeln!(tex_file, "{}", equation.0)?,
+                    Layout::Paragraph { plain } => {
+                        let text = if plain {
+                            escape(&equation.0)
+                        } else {
+                            equation.0.to_string()
+                        };
+                        writeln!(tex_file, "\\parbox{{\\linewidth}}{{\\raggedright")?;
+                        writeln!(tex_file, "{}", text)?;
+                        writeln!(tex_file, "}}")?;
+                    }
                 }
                 writeln!(tex_file, "}} }}")?;
                 writeln!(tex_file, "\\end{{equation*}}")?;
@@ -195,129 +719,395 @@ fn create_tex(tex_path: &Path) -> IResult<()> {
 
     writeln!(tex_file, "{}", LATEX_POSTLUDE)?;
 
-    Ok(())
+    File::create(tex_path)?.write_all(&tex_file)?;
+    Ok(starts)
 }
 
-fn render_tex(tex_path: &Path, pdf_path: &Path, raw_path: &Path) -> IResult<()> {
-    let out = Command::new("pdflatex")
-        .current_dir(tex_path.parent().unwrap())
-        .arg(tex_path.file_name().unwrap())
-        .output()
-        .expect("Can't make command");
+fn render_tex(
+    tex_path: &Path,
+    pdf_path: &Path,
+    raw_path: &Path,
+    backend: &LatexBackend,
+    starts: &[(usize, usize)],
+) -> Result<(), LatexError> {
+    compile_tex(tex_path, backend, starts)?;
+
+    let rasterizer = backend.rasterizer;
+    let out = run(
+        rasterizer.program(),
+        &mut rasterizer.command(pdf_path, raw_path, backend.dpi),
+    )?;
 
     if !out.status.success() {
-        eprintln!("Latex compile error:");
-        eprintln!("{}", String::from_utf8_lossy(&out.stderr));
-        exit(1);
+        return Err(LatexError::ToolFailed {
+            tool: rasterizer.program().to_string(),
+            output: String::from_utf8_lossy(&out.stderr).trim().to_string(),
+        });
     }
 
-    let out = Command::new("pdftoppm")
-        .arg(pdf_path.as_os_str())
-        .arg(raw_path.as_os_str())
-        .arg("-r")
-        .arg("250")
-        .arg("-png")
-        .output()
-        .expect("Can't make command");
+    Ok(())
+}
+
+/// Compile the document into a `.pdf`-file, see [`create_tex`] for `starts`
+///
+/// [`create_tex`]: fn.create_tex.html
+fn compile_tex(
+    tex_path: &Path,
+    backend: &LatexBackend,
+    starts: &[(usize, usize)],
+) -> Result<(), LatexError> {
+    let engine = backend.engine;
+    let out = run(
+        engine.program(),
+        &mut engine.command(tex_path, &backend.engine_args),
+    )?;
 
     if !out.status.success() {
-        eprintln!("pdftoppm error");
-        eprintln!("{}", String::from_utf8_lossy(&out.stderr));
-        exit(1);
+        let log = read(tex_path.with_extension("log")).unwrap_or_default();
+        return Err(compile_error(&String::from_utf8_lossy(&log), &out, starts));
     }
 
     Ok(())
 }
 
-fn read_pngs(path: &Path) -> IResult<()> {
-    if let Ok(ref mut eqs) = EQUATIONS.lock() {
-        let digits_max = format!("{}", eqs.len()).len();
-
-        for (i, (_, _, ref mut im)) in eqs.iter_mut().enumerate() {
-            let num_red = zero_pad(format!("{}", 2 * i + 1), digits_max);
-            let num_blue = zero_pad(format!("{}", 2 * i + 2), digits_max);
+/// Run a program, telling whether it's missing
+fn run(tool: &str, command: &mut Command) -> Result<Output, LatexError> {
+    command.output().map_err(|e| {
+        if e.kind() == ErrorKind::NotFound {
+            LatexError::MissingTool(tool.to_string())
+        } else {
+            LatexError::from(e)
+        }
+    })
+}
 
-            let mut img_path_red = path.to_path_buf();
-            img_path_red.push(format!("tmp-res-{}.png", num_red));
+/// Find the errors in the log of the engine, and which expressions they're in, see `parse_log`
+fn compile_error(log: &str, out: &Output, starts: &[(usize, usize)]) -> LatexError {
+    let eqs = EQUATIONS.lock();
+    let mut errors: Vec<CompileError> = Vec::new();
+    for (message, number) in parse_log(log) {
+        let index = number.and_then(|number| expression_at(starts, number));
+        let expression = match (index, &eqs) {
+            (Some(index), Ok(eqs)) => Some(eqs[index].0.to_string()),
+            _ => None,
+        };
 
-            let mut img_path_blue = path.to_path_buf();
-            img_path_blue.push(format!("tmp-res-{}.png", num_blue));
+        // Each expression is in the document twice, so each error is found twice
+        let error = CompileError {
+            index,
+            expression,
+            message: message.to_string(),
+        };
+        if !errors.contains(&error) {
+            errors.push(error);
+        }
+    }
 
-            let mut im_red_res = PngImage::load_from_path(File::open(img_path_red)?)
-                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    if errors.is_empty() {
+        // Most engines print the errors on stdout, but tectonic prints them on stderr
+        let output = format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&out.stdout),
+            String::from_utf8_lossy(&out.stderr)
+        );
+        errors.push(CompileError {
+            index: None,
+            expression: None,
+            message: output.trim().to_string(),
+        });
+    }
 
-            let im_blue = PngImage::load_from_path(File::open(img_path_blue)?)
-                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    LatexError::Compile(errors)
+}
 
-            let mut maxx = 0;
-            let mut maxy = 0;
-            let mut minx = im_red_res.width;
-            let mut miny = im_red_res.height;
+/// Find the errors in the log of the engine, together with the line in the document they're on,
+/// if it's known. Each error starts with a line like `! Undefined control sequence.`, followed by
+/// the line it's on, such as `l.23 $ \frac{1}{ $`.
+fn parse_log(log: &str) -> Vec<(&str, Option<usize>)> {
+    let mut found = Vec::new();
+    let mut message: Option<&str> = None;
+    for line in log.lines() {
+        if let Some(error) = line.strip_prefix("! ") {
+            if let Some(message) = message.take() {
+                found.push((message, None));
+            }
+            message = Some(error.trim());
+        } else if let Some(position) = line.strip_prefix("l.") {
+            if let Some(message) = message.take() {
+                let number = position
+                    .split(|ch: char| !ch.is_ascii_digit())
+                    .next()
+                    .and_then(|number| number.parse::<usize>().ok());
+                found.push((message, number));
+            }
+        }
+    }
+    if let Some(message) = message {
+        found.push((message, None));
+    }
+    found
+}
 
-            for i in 0..im_red_res.width * im_red_res.height {
-                let x = i % im_red_res.width;
-                let y = i / im_red_res.width;
+/// The expression a line in the document belongs to, given the line each expression starts on,
+/// in order, as returned by `create_tex`. Lines before the first expression are in the prelude.
+fn expression_at(starts: &[(usize, usize)], line: usize) -> Option<usize> {
+    starts
+        .iter()
+        .rev()
+        .find(|&&(start, _)| start <= line)
+        .map(|&(_, idx)| idx)
+}
 
-                let rr = im_red_res.data[4 * i];
-                let rg = im_red_res.data[4 * i];
-                let rb = im_red_res.data[4 * i + 2];
+fn read_pngs(path: &Path, setup: &Setup, missing: &[usize]) -> IResult<()> {
+    // Decoding takes a while, so the expressions are copied out instead of keeping them locked
+    let sources: Vec<(Cow<'static, str>, Layout)> = match EQUATIONS.lock() {
+        Ok(eqs) => missing
+            .iter()
+            .map(|&idx| (eqs[idx].0.clone(), eqs[idx].1))
+            .collect(),
+        Err(_) => return Ok(()),
+    };
 
-                let br = im_blue.data[4 * i];
-                let bb = im_blue.data[4 * i + 2];
+    // Each expression is rendered twice
+    let pages = 2 * missing.len();
+    let prefix = path.join("tmp-res");
+    let rasterizer = setup.backend.rasterizer;
 
-                let rdiff = rr as i16 - br as i16;
-                let bdiff = bb as i16 - rb as i16;
+    let mut images = Vec::with_capacity(missing.len());
+    for (i, (equation, layout)) in sources.iter().enumerate() {
+        let img_path_red = rasterizer.page_path(&prefix, 2 * i + 1, pages);
+        let img_path_blue = rasterizer.page_path(&prefix, 2 * i + 2, pages);
 
-                let alpha = 255 - (rdiff + bdiff) / 2;
-                let alpha = alpha.min(255).max(0) as u8;
+        let image = read_png_pair(&img_path_red, &img_path_blue)?;
 
-                im_red_res.data[4 * i] = br;
-                im_red_res.data[4 * i + 2] = rb;
-                im_red_res.data[4 * i + 3] = alpha;
+        let mut png = Vec::new();
+        if image.save_to_path(&mut png).is_ok() {
+            let key = cache_key(setup, equation, *layout);
+            if let Err(e) = diskcache::write("latex", key, &png) {
+                eprintln!("Couldn't cache `{}`: {}", equation, e);
+            }
+        }
 
-                if (br < 250 || rg < 250 || rb < 250) && alpha > 250 {
-                    maxx = maxx.max(x + 1);
-                    maxy = maxy.max(y + 1);
+        images.push(image);
+    }
 
-                    minx = minx.min(x);
-                    miny = miny.min(y);
-                }
-            }
-            // Margins
-            maxx = (maxx + 3).min(im_red_res.width - 1);
-            maxy = (maxy + 3).min(im_red_res.height - 1);
-            minx = minx.saturating_sub(3);
-            miny = miny.saturating_sub(3);
-
-            let width = maxx - minx;
-            let height = maxy - miny;
-            let mut resdata = vec![0; 4 * width * height];
-
-            for x in 0..width {
-                for y in 0..height {
-                    let i_r = y * width + x;
-                    let i_l = (y + miny) * im_red_res.width + x + minx;
-
-                    resdata[4 * i_r] = im_red_res.data[4 * i_l];
-                    resdata[4 * i_r + 1] = im_red_res.data[4 * i_l + 1];
-                    resdata[4 * i_r + 2] = im_red_res.data[4 * i_l + 2];
-                    resdata[4 * i_r + 3] = im_red_res.data[4 * i_l + 3];
-                }
+    if let Ok(ref mut eqs) = EQUATIONS.lock() {
+        for (&idx, image) in missing.iter().zip(images) {
+            // Another render might have finished first
+            if let RenderState::Missing = eqs[idx].2 {
+                eqs[idx].2 = RenderState::Rendered(image);
             }
-
-            *im = Some(PngImage {
-                data: resdata,
-                width,
-                height,
-            });
         }
     }
     Ok(())
 }
 
-fn zero_pad(n: String, len: usize) -> String {
-    let needed = len.saturating_sub(n.len());
-    let mut res = (0..needed).map(|_| '0').collect::<String>();
-    res.push_str(&n);
-    res
+/// Combine an expression rendered in red and blue into one image with transparency, and crop
+/// it to the expression
+fn read_png_pair(red_path: &Path, blue_path: &Path) -> IResult<PngImage> {
+    let mut im_red_res = PngImage::load_from_path(File::open(red_path)?)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    let im_blue = PngImage::load_from_path(File::open(blue_path)?)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    let mut maxx = 0;
+    let mut maxy = 0;
+    let mut minx = im_red_res.width;
+    let mut miny = im_red_res.height;
+
+    for i in 0..im_red_res.width * im_red_res.height {
+        let x = i % im_red_res.width;
+        let y = i / im_red_res.width;
+
+        let rr = im_red_res.data[4 * i];
+        let rg = im_red_res.data[4 * i];
+        let rb = im_red_res.data[4 * i + 2];
+
+        let br = im_blue.data[4 * i];
+        let bb = im_blue.data[4 * i + 2];
+
+        let rdiff = rr as i16 - br as i16;
+        let bdiff = bb as i16 - rb as i16;
+
+        let alpha = 255 - (rdiff + bdiff) / 2;
+        let alpha = alpha.min(255).max(0) as u8;
+
+        im_red_res.data[4 * i] = br;
+        im_red_res.data[4 * i + 2] = rb;
+        im_red_res.data[4 * i + 3] = alpha;
+
+        if (br < 250 || rg < 250 || rb < 250) && alpha > 250 {
+            maxx = maxx.max(x + 1);
+            maxy = maxy.max(y + 1);
+
+            minx = minx.min(x);
+            miny = miny.min(y);
+        }
+    }
+    // Margins
+    maxx = (maxx + 3).min(im_red_res.width - 1);
+    maxy = (maxy + 3).min(im_red_res.height - 1);
+    minx = minx.saturating_sub(3);
+    miny = miny.saturating_sub(3);
+
+    let width = maxx - minx;
+    let height = maxy - miny;
+    let mut resdata = vec![0; 4 * width * height];
+
+    for x in 0..width {
+        for y in 0..height {
+            let i_r = y * width + x;
+            let i_l = (y + miny) * im_red_res.width + x + minx;
+
+            resdata[4 * i_r] = im_red_res.data[4 * i_l];
+            resdata[4 * i_r + 1] = im_red_res.data[4 * i_l + 1];
+            resdata[4 * i_r + 2] = im_red_res.data[4 * i_l + 2];
+            resdata[4 * i_r + 3] = im_red_res.data[4 * i_l + 3];
+        }
+    }
+
+    Ok(PngImage::new(width, height, resdata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile_errors(group: &[usize], bad: &[usize]) -> Result<(), LatexError> {
+        let errors: Vec<CompileError> = group
+            .iter()
+            .filter(|idx| bad.contains(idx))
+            .map(|idx| CompileError {
+                index: None,
+                expression: None,
+                message: format!("bad {}", idx),
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(LatexError::Compile(errors))
+        }
+    }
+
+    #[test]
+    fn parse_log_finds_errors_and_lines() {
+        let log = "This is pdfTeX\n\
+                   ! Undefined control sequence.\n\
+                   l.12 $ \\foo $\n\
+                   ! Missing $ inserted.\n\
+                   <inserted text>\n\
+                   l.20 $ x^ $\n\
+                   ! Emergency stop.\n";
+
+        assert_eq!(
+            parse_log(log),
+            vec![
+                ("Undefined control sequence.", Some(12)),
+                ("Missing $ inserted.", Some(20)),
+                ("Emergency stop.", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn expression_at_maps_lines_to_expressions() {
+        let starts = [(10, 3), (18, 7)];
+
+        assert_eq!(expression_at(&starts, 5), None);
+        assert_eq!(expression_at(&starts, 10), Some(3));
+        assert_eq!(expression_at(&starts, 17), Some(3));
+        assert_eq!(expression_at(&starts, 25), Some(7));
+        assert_eq!(expression_at(&[], 25), None);
+    }
+
+    #[test]
+    fn bisect_finds_broken_expressions() {
+        let group: Vec<usize> = (0..8).collect();
+        let bad = [2, 5];
+        let errors = match compile_errors(&group, &bad) {
+            Err(LatexError::Compile(errors)) => errors,
+            result => panic!("Expected compile errors, got {:?}", result),
+        };
+
+        let mut renders = 0;
+        let mut broken = Vec::new();
+        bisect(
+            &group,
+            errors,
+            &mut |half| {
+                renders += 1;
+                compile_errors(half, &bad)
+            },
+            &mut broken,
+        )
+        .unwrap();
+
+        assert_eq!(
+            broken,
+            vec![
+                (2, vec!["bad 2".to_string()]),
+                (5, vec!["bad 5".to_string()]),
+            ]
+        );
+        // The two halves, the four quarters, and the single expressions of the two broken quarters
+        assert_eq!(renders, 10);
+    }
+
+    #[test]
+    fn bisect_merges_repeated_messages() {
+        let error = CompileError {
+            index: None,
+            expression: None,
+            message: "Undefined control sequence.".to_string(),
+        };
+
+        let mut broken = Vec::new();
+        bisect(
+            &[4],
+            vec![error.clone(), error],
+            &mut |_| panic!("A single expression isn't split"),
+            &mut broken,
+        )
+        .unwrap();
+
+        assert_eq!(
+            broken,
+            vec![(4, vec!["Undefined control sequence.".to_string()])]
+        );
+    }
+
+    #[test]
+    fn bisect_stops_on_other_errors() {
+        let mut broken = Vec::new();
+        let result = bisect(
+            &[0, 1, 2],
+            Vec::new(),
+            &mut |_| Err(LatexError::MissingTool("pdflatex".to_string())),
+            &mut broken,
+        );
+
+        assert_eq!(result, Err(LatexError::MissingTool("pdflatex".to_string())));
+        assert!(broken.is_empty());
+    }
+
+    #[test]
+    fn cache_key_depends_on_everything_rendered() {
+        let setup = Setup {
+            prelude: document_prelude(LatexEngine::PdfLatex),
+            backend: LatexBackend::default(),
+        };
+        let key = cache_key(&setup, "x^2", Layout::Math);
+
+        assert_eq!(key, cache_key(&setup, "x^2", Layout::Math));
+        assert_ne!(key, cache_key(&setup, "x^3", Layout::Math));
+        assert_ne!(key, cache_key(&setup, "x^2", Layout::Text));
+
+        let sharper = Setup {
+            prelude: setup.prelude.clone(),
+            backend: LatexBackend::default().with_dpi(300),
+        };
+        assert_ne!(key, cache_key(&sharper, "x^2", Layout::Math));
+    }
 }
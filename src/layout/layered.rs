@@ -2,10 +2,7 @@
 
 extern crate sdl2;
 
-use sdl2::render::Canvas;
-use sdl2::video::Window;
-
-use drawable::{DrawSettings, Drawable, Position, State};
+use drawable::{DrawSettings, DrawTarget, Drawable, Position, State};
 
 /// I'm not sure why this is needed, but when just storing the dyn Drawable, the compiler complains about
 /// Layered::content_mut
@@ -33,6 +30,8 @@ pub struct Layered {
     update_seq: bool,
     /// The object to be layered
     content: Vec<Box<dyn Layerable>>,
+    /// The indices of the content stepped in each step, to be able to step back
+    history: Vec<Vec<usize>>,
 }
 
 impl Layered {
@@ -41,6 +40,7 @@ impl Layered {
         Layered {
             content,
             update_seq,
+            history: Vec::new(),
         }
     }
 }
@@ -57,31 +57,41 @@ impl Drawable for Layered {
             .collect()
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, pos: &Position, settings: DrawSettings) {
+    fn draw(&self, canvas: &mut dyn DrawTarget, pos: &Position, settings: DrawSettings) {
         for obj in &self.content {
             obj.draw(canvas, pos, settings);
         }
     }
 
     fn step(&mut self) {
-        let mut any_stepped = false;
-        for item in &mut self.content {
+        let mut stepped = Vec::new();
+        for (i, item) in self.content.iter_mut().enumerate() {
             if item.state() == State::Working {
                 item.step();
-                any_stepped = true;
+                stepped.push(i);
                 if self.update_seq {
-                    return;
+                    break;
                 }
             }
         }
 
-        if !any_stepped {
-            for item in &mut self.content {
+        if stepped.is_empty() {
+            for (i, item) in self.content.iter_mut().enumerate() {
                 if item.state() == State::Final {
                     item.step();
+                    stepped.push(i);
                 }
             }
         }
+        self.history.push(stepped);
+    }
+
+    fn step_back(&mut self) {
+        if let Some(stepped) = self.history.pop() {
+            for i in stepped {
+                self.content[i].step_back();
+            }
+        }
     }
 
     fn state(&self) -> State {
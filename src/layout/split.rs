@@ -4,10 +4,8 @@ extern crate sdl2;
 
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
 
-use drawable::{DrawSettings, Drawable, Position, State};
+use drawable::{DrawSettings, DrawTarget, Drawable, Position, State};
 
 use super::Orientation;
 
@@ -19,6 +17,13 @@ pub enum UpdateOrder {
     SecondFirst,
 }
 
+/// One of the two sides of a split
+#[derive(Clone, Copy)]
+enum Side {
+    First,
+    Second,
+}
+
 /// A split at a centain percent
 pub struct Split<T: Drawable, U: Drawable> {
     /// How to split the window. The argument is the size of the window in the splitting direction
@@ -33,6 +38,8 @@ pub struct Split<T: Drawable, U: Drawable> {
     pub first: T,
     /// The second content
     pub second: U,
+    /// The sides stepped in each step, to be able to step back
+    history: Vec<Vec<Side>>,
 }
 
 unsafe impl<T: Drawable, U: Drawable> Send for Split<T, U> {}
@@ -52,6 +59,7 @@ impl<T: Drawable, U: Drawable> Split<T, U> {
             order,
             first,
             second,
+            history: Vec::new(),
         }
     }
 
@@ -70,6 +78,7 @@ impl<T: Drawable, U: Drawable> Split<T, U> {
             order,
             first,
             second,
+            history: Vec::new(),
         }
     }
 
@@ -87,6 +96,16 @@ impl<T: Drawable, U: Drawable> Split<T, U> {
             order,
             first,
             second,
+            history: Vec::new(),
+        }
+    }
+
+    fn step_sides(&mut self, sides: &[Side]) {
+        for side in sides {
+            match side {
+                Side::First => self.first.step(),
+                Side::Second => self.second.step(),
+            }
         }
     }
 }
@@ -100,7 +119,7 @@ impl<T: Drawable, U: Drawable> Drawable for Split<T, U> {
         vec![&mut self.first, &mut self.second]
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, pos: &Position, settings: DrawSettings) {
+    fn draw(&self, canvas: &mut dyn DrawTarget, pos: &Position, settings: DrawSettings) {
         match pos {
             Position::TopLeftCorner(_) | Position::Center(_) => {
                 eprintln!("Trying to draw a Splitpane not using a Position::Rect. Please don't");
@@ -148,37 +167,54 @@ impl<T: Drawable, U: Drawable> Drawable for Split<T, U> {
     }
 
     fn step(&mut self) {
+        let mut stepped = Vec::new();
         match self.order {
             UpdateOrder::Simultaneous => {
                 if self.first.state() == State::Working {
-                    self.first.step();
+                    stepped.push(Side::First);
                 }
                 if self.second.state() == State::Working {
-                    self.second.step();
+                    stepped.push(Side::Second);
                 }
+                self.step_sides(&stepped);
                 if self.first.state() >= State::Final && self.second.state() >= State::Final {
-                    self.first.step();
-                    self.second.step();
+                    self.step_sides(&[Side::First, Side::Second]);
+                    stepped.push(Side::First);
+                    stepped.push(Side::Second);
                 }
             }
             UpdateOrder::FirstSecond => {
                 if self.first.state() == State::Working {
-                    self.first.step();
+                    stepped.push(Side::First);
                 } else if self.second.state() == State::Working {
-                    self.second.step();
+                    stepped.push(Side::Second);
                 } else {
-                    self.first.step();
-                    self.second.step();
+                    stepped.push(Side::First);
+                    stepped.push(Side::Second);
                 }
+                self.step_sides(&stepped);
             }
             UpdateOrder::SecondFirst => {
                 if self.second.state() == State::Working {
-                    self.second.step();
+                    stepped.push(Side::Second);
                 } else if self.first.state() == State::Working {
-                    self.first.step();
+                    stepped.push(Side::First);
                 } else {
-                    self.first.step();
-                    self.second.step();
+                    stepped.push(Side::First);
+                    stepped.push(Side::Second);
+                }
+                self.step_sides(&stepped);
+            }
+        }
+        self.history.push(stepped);
+    }
+
+    fn step_back(&mut self) {
+        if let Some(stepped) = self.history.pop() {
+            for side in stepped.into_iter().rev() {
+                match side {
+                    Side::First => self.first.step_back(),
+                    Side::Second => self.second.step_back(),
                 }
             }
         }
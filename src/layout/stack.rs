@@ -4,11 +4,9 @@ extern crate sdl2;
 
 use sdl2::pixels::Color;
 use sdl2::rect::Point;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
 
 use super::Orientation;
-use drawable::{DrawSettings, Drawable, KnownSize, Position, State};
+use drawable::{DrawSettings, DrawTarget, Drawable, KnownSize, Position, State};
 
 /// Positioning the elements
 #[allow(missing_docs)]
@@ -45,6 +43,8 @@ pub struct Stack {
     pub update_seq: bool,
     /// The content in the stack
     pub content: Vec<Box<dyn Stackable>>,
+    /// The indices of the content stepped in each step, to be able to step back
+    history: Vec<Vec<usize>>,
 }
 
 impl Stack {
@@ -62,6 +62,7 @@ impl Stack {
             positioning,
             update_seq,
             content,
+            history: Vec::new(),
         }
     }
 }
@@ -78,7 +79,7 @@ impl<'a> Drawable for Stack {
             .collect()
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, pos: &Position, settings: DrawSettings) {
+    fn draw(&self, canvas: &mut dyn DrawTarget, pos: &Position, settings: DrawSettings) {
         let rect = pos.into_rect_with_size(self.width() as u32, self.height() as u32);
         let corner = rect.top_left();
         if settings.notes_view {
@@ -141,23 +142,33 @@ impl<'a> Drawable for Stack {
     }
 
     fn step(&mut self) {
-        let mut any_stepped = false;
-        for item in &mut self.content {
+        let mut stepped = Vec::new();
+        for (i, item) in self.content.iter_mut().enumerate() {
             if item.state() == State::Working {
                 item.step();
-                any_stepped = true;
+                stepped.push(i);
                 if self.update_seq {
-                    return;
+                    break;
                 }
             }
         }
-        if !any_stepped {
-            for item in &mut self.content {
+        if stepped.is_empty() {
+            for (i, item) in self.content.iter_mut().enumerate() {
                 if item.state() == State::Final {
                     item.step();
+                    stepped.push(i);
                 }
             }
         }
+        self.history.push(stepped);
+    }
+
+    fn step_back(&mut self) {
+        if let Some(stepped) = self.history.pop() {
+            for i in stepped {
+                self.content[i].step_back();
+            }
+        }
     }
 
     fn state(&self) -> State {
@@ -16,6 +16,7 @@
 
 #![warn(missing_docs)]
 
+extern crate chrono;
 #[macro_use]
 extern crate lazy_static;
 extern crate png;
@@ -25,13 +26,19 @@ extern crate tempfile;
 pub extern crate sdl2;
 
 pub mod anchor;
+pub mod diskcache;
 pub mod ditherer;
 pub mod drawable;
 pub mod empty;
+pub mod export;
 pub mod image;
 pub mod latex;
 pub mod layout;
 pub mod margin;
+pub mod notes;
+pub mod presenter;
+pub mod rendered;
+pub mod scale;
 pub mod scene;
 pub mod solid;
 pub mod window;
@@ -42,7 +49,7 @@ pub mod prelude {
     //! A "prelude" to avoid having to `use` a gazillion different things
     pub use anchor::{Anchor, AnchorDirection};
     pub use ditherer::{alpha_dither_fn, color_dither_fn, DitherDirection, Ditherer};
-    pub use drawable::{Drawable, DrawSettings, Position, State};
+    pub use drawable::{Drawable, DrawSettings, DrawTarget, Position, State};
     pub use empty::Empty;
     pub use image::PngImage;
     pub use latex::render::add_prelude;
@@ -52,9 +59,14 @@ pub mod prelude {
     pub use layout::stack::{ElementPositioning, Stack};
     pub use layout::Orientation;
     pub use margin::Margin;
+    pub use notes::{Note, Notes};
+    pub use rendered::Rendered;
+    pub use scale::{Scale, ScaleMode, ScaleToFit};
     pub use scene::{DrawableWrapper, Scene, SceneList, Action};
     pub use solid::Solid;
-    pub use window::{default_settings, WindowManager, WindowManagerSettings, YEvent};
+    pub use window::{
+        default_settings, projector_settings, WindowManager, WindowManagerSettings, YEvent,
+    };
     pub use withsize::WithSize;
     pub use utils;
 
@@ -2,10 +2,8 @@
 
 use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
-use sdl2::render::Canvas;
-use sdl2::video::Window;
 
-use drawable::{DrawSettings, Drawable, KnownSize, Position, State};
+use drawable::{DrawSettings, DrawTarget, Drawable, KnownSize, Position, State};
 
 /// A wrapper around a Drawable with KnownSize, giving it a margin on all sides
 pub struct Margin<T: Drawable + KnownSize> {
@@ -52,11 +50,15 @@ impl<T: Drawable + KnownSize> Drawable for Margin<T> {
         self.inner.step()
     }
 
+    fn step_back(&mut self) {
+        self.inner.step_back()
+    }
+
     fn state(&self) -> State {
         self.inner.state()
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, pos: &Position, settings: DrawSettings) {
+    fn draw(&self, canvas: &mut dyn DrawTarget, pos: &Position, settings: DrawSettings) {
         match pos {
             Position::Rect(r) => {
                 let r2 = Rect::new(
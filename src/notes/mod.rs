@@ -0,0 +1,239 @@
+//! Speaker notes, attached to objects and shown in the presenter view
+
+use std::borrow::Cow;
+
+use sdl2::rect::{Point, Rect};
+
+use drawable::{draw_scaled, DrawSettings, DrawTarget, Drawable, KnownSize, Position, State};
+use image::PngImage;
+use latex::render::{read_image, register_paragraph, LatexIdx};
+
+/// Space between notes in the presenter view, in pixels
+const NOTE_MARGIN: u32 = 10;
+
+/// A speaker note, rendered with LaTeX as paragraphs wrapped at the width of the page
+pub struct Note {
+    /// The content of the note
+    pub text: Cow<'static, str>,
+    /// Is the note LaTeX in text mode? Otherwise, it's plain text, shown as written.
+    pub is_latex: bool,
+    image: Option<PngImage>,
+    id: Option<LatexIdx>,
+}
+
+impl Note {
+    /// Create a note containing plain text. Empty lines separate paragraphs.
+    ///
+    /// ```
+    /// use ytesrev::notes::Note;
+    ///
+    /// let note = Note::text("Remember to breathe");
+    /// assert!(!note.is_latex);
+    /// ```
+    pub fn text<S: Into<Cow<'static, str>>>(text: S) -> Note {
+        Note {
+            text: text.into(),
+            is_latex: false,
+            image: None,
+            id: None,
+        }
+    }
+
+    /// Create a note containing LaTeX in text mode, such as `The answer is $\pi$`
+    ///
+    /// ```
+    /// use ytesrev::notes::Note;
+    ///
+    /// let note = Note::latex("Mention that $e^{i \\pi} = -1$");
+    /// assert!(note.is_latex);
+    /// ```
+    pub fn latex<S: Into<Cow<'static, str>>>(text: S) -> Note {
+        Note {
+            text: text.into(),
+            is_latex: true,
+            image: None,
+            id: None,
+        }
+    }
+
+    fn register(&mut self) {
+        self.id = Some(register_paragraph(self.text.clone(), !self.is_latex));
+    }
+
+    fn load(&mut self) {
+        if let Some(id) = self.id.take() {
+            match read_image(id) {
+                Ok(image) => {
+                    self.image = Some(image);
+                }
+                Err(e) => {
+                    eprintln!("Couldn't load image for note `{}`: {}", self.text, e);
+                }
+            }
+        } else {
+            eprintln!("Wrong loading order!");
+        }
+    }
+}
+
+/// A wrapper attaching speaker notes to an object. The notes are only shown in the presenter view,
+/// in windows with `notes_view` set, and are drawn by the [`Scene`] using [`draw_notes`].
+///
+/// Each note is attached to a step of the inner object, and is shown from that step until the next
+/// note, as long as the object isn't hidden. A note at step 0 is shown from the start, so wrapping
+/// an entire scene attaches notes to the whole scene, and wrapping an object in a [`Stack`]
+/// attaches notes to the step showing it.
+///
+/// ```
+/// use ytesrev::prelude::*;
+/// use ytesrev::notes::{Note, Notes};
+///
+/// let notes = Notes::new(Ditherer::new(LatexObj::text("Hello")))
+///     .with_note(0, Note::text("Say hello"))
+///     .with_note(1, Note::text("Wait for applause"));
+/// assert_eq!(notes.notes().len(), 1);
+/// assert_eq!(notes.notes()[0].text, "Say hello");
+/// ```
+///
+/// [`Scene`]: ../scene/trait.Scene.html
+/// [`Stack`]: ../layout/stack/struct.Stack.html
+/// [`draw_notes`]: fn.draw_notes.html
+pub struct Notes<T: Drawable> {
+    /// The inner object
+    pub inner: T,
+    /// The notes, together with the step they're attached to, in order
+    pub notes: Vec<(usize, Note)>,
+    steps: usize,
+}
+
+impl<T: Drawable> Notes<T> {
+    /// Create a new Notes wrapper, without any notes
+    pub fn new(inner: T) -> Notes<T> {
+        Notes {
+            inner,
+            notes: Vec::new(),
+            steps: 0,
+        }
+    }
+
+    /// Attach a note to a specific step of the inner object, zero being the start
+    pub fn with_note(mut self, step: usize, note: Note) -> Notes<T> {
+        let idx = self
+            .notes
+            .iter()
+            .position(|(at, _)| *at > step)
+            .unwrap_or(self.notes.len());
+        self.notes.insert(idx, (step, note));
+        self
+    }
+
+    /// The note that should be shown in the current step, if there is any
+    pub fn current_note(&self) -> Option<&Note> {
+        self.notes
+            .iter()
+            .rev()
+            .find(|(at, _)| *at <= self.steps)
+            .map(|(_, note)| note)
+    }
+}
+
+impl<T: Drawable> Drawable for Notes<T> {
+    fn content(&self) -> Vec<&dyn Drawable> {
+        vec![&self.inner]
+    }
+
+    fn content_mut(&mut self) -> Vec<&mut dyn Drawable> {
+        vec![&mut self.inner]
+    }
+
+    fn register(&mut self) {
+        self.inner.register();
+        for (_, note) in &mut self.notes {
+            note.register();
+        }
+    }
+
+    fn load(&mut self) {
+        self.inner.load();
+        for (_, note) in &mut self.notes {
+            note.load();
+        }
+    }
+
+    fn step(&mut self) {
+        self.steps += 1;
+        self.inner.step();
+    }
+
+    fn step_back(&mut self) {
+        self.steps = self.steps.saturating_sub(1);
+        self.inner.step_back();
+    }
+
+    fn state(&self) -> State {
+        self.inner.state()
+    }
+
+    fn notes(&self) -> Vec<&Note> {
+        if self.inner.state() == State::Hidden {
+            return Vec::new();
+        }
+
+        let mut notes: Vec<&Note> = self.current_note().into_iter().collect();
+        notes.extend(self.inner.notes());
+        notes
+    }
+
+    fn draw(&self, canvas: &mut dyn DrawTarget, position: &Position, settings: DrawSettings) {
+        self.inner.draw(canvas, position, settings);
+    }
+}
+
+impl<T: Drawable + KnownSize> KnownSize for Notes<T> {
+    fn width(&self) -> usize {
+        self.inner.width()
+    }
+
+    fn height(&self) -> usize {
+        self.inner.height()
+    }
+}
+
+/// Draw notes below each other, starting at the top of the position. Notes wider than the position
+/// are scaled down to fit.
+pub fn draw_notes(
+    notes: &[&Note],
+    canvas: &mut dyn DrawTarget,
+    position: &Position,
+    settings: DrawSettings,
+) {
+    let rect = match *position {
+        Position::Rect(rect) => rect,
+        _ => {
+            let viewport = canvas.viewport();
+            position.into_rect_with_size(viewport.width(), viewport.height())
+        }
+    };
+    let mut y = rect.y();
+
+    for image in notes.iter().filter_map(|note| note.image.as_ref()) {
+        let scale = (rect.width() as f64 / image.width as f64).min(1.);
+        let width = (image.width as f64 * scale) as u32;
+        let height = (image.height as f64 * scale) as u32;
+
+        if y + height as i32 > rect.bottom() {
+            break;
+        }
+
+        draw_scaled(
+            canvas,
+            Rect::new(rect.x(), y, width.max(1), height.max(1)),
+            scale,
+            |canvas| {
+                image.draw(canvas, &Position::TopLeftCorner(Point::new(0, 0)), settings);
+            },
+        );
+
+        y += (height + NOTE_MARGIN) as i32;
+    }
+}
@@ -0,0 +1,227 @@
+//! The presenter view, shown in windows with `notes_view` set. It shows the current slide, a
+//! preview of the next step, the speaker notes, and a few clocks to keep the talk on time. The
+//! slide being entered to go to is shown below the clocks.
+
+extern crate chrono;
+extern crate sdl2;
+
+use std::time::{Duration, Instant};
+
+use chrono::{Local, Timelike};
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+
+use drawable::{draw_scaled, DrawSettings, DrawTarget, Position};
+use scene::{Action, Scene};
+use utils::seven_segment;
+use window::draw_scene;
+
+/// Space between everything in the presenter view, in pixels
+const MARGIN: u32 = 10;
+/// How much of the width the current slide takes up
+const CURRENT_SIZE: f64 = 0.6;
+/// The height of the clocks, in relation to the height of the window
+const CLOCK_SIZE: f64 = 0.07;
+
+const CLOCK_COLOR: Color = Color {
+    r: 0,
+    g: 0,
+    b: 0,
+    a: 255,
+};
+const OVERTIME_COLOR: Color = Color {
+    r: 255,
+    g: 0,
+    b: 0,
+    a: 255,
+};
+const GOTO_COLOR: Color = Color {
+    r: 0,
+    g: 0,
+    b: 255,
+    a: 255,
+};
+const BORDER_COLOR: Color = Color {
+    r: 128,
+    g: 128,
+    b: 128,
+    a: 255,
+};
+
+/// Keeps track of how long the talk has been going on
+pub struct TalkTimer {
+    /// When the talk started, if it has
+    pub started: Option<Instant>,
+    /// How long the talk should be, if there's a limit
+    pub talk_length: Option<Duration>,
+}
+
+impl TalkTimer {
+    /// Create a new timer that hasn't started yet
+    pub fn new(talk_length: Option<Duration>) -> TalkTimer {
+        TalkTimer {
+            started: None,
+            talk_length,
+        }
+    }
+
+    /// Start the timer, if it hasn't been started before
+    pub fn start(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    /// How long the talk has been going on
+    pub fn elapsed(&self) -> Duration {
+        self.started
+            .map(|started| started.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+
+    /// How many seconds are left of the talk, negative if the talk has gone over time. `None` if
+    /// there's no limit.
+    pub fn remaining(&self) -> Option<i64> {
+        self.talk_length
+            .map(|length| length.as_secs() as i64 - self.elapsed().as_secs() as i64)
+    }
+}
+
+/// Draw the presenter view of a scene, clearing the target with the background color first. The
+/// preview is another instance of the same scene, kept one step ahead of it, see [`WindowManager::with_preview`].
+/// `goto_input` is the slide being entered to go to, if any, see
+/// [`WindowManagerSettings::goto_input_rule`].
+///
+/// [`WindowManager::with_preview`]: ../window/struct.WindowManager.html#method.with_preview
+/// [`WindowManagerSettings::goto_input_rule`]: ../window/struct.WindowManagerSettings.html#structfield.goto_input_rule
+pub fn draw_presenter<T: Scene + ?Sized>(
+    scene: &T,
+    preview: Option<&T>,
+    timer: &TalkTimer,
+    goto_input: &str,
+    canvas: &mut dyn DrawTarget,
+    settings: DrawSettings,
+) {
+    let (width, height) = canvas.output_size().expect("Can't get size");
+
+    let (r, g, b) = settings.background_color;
+    canvas.set_draw_color(Color::RGB(r, g, b));
+    canvas.clear();
+
+    // The slides should look like they do in the main window
+    let slide_settings = DrawSettings {
+        notes_view: false,
+        logical_size: settings.logical_size.or(Some((width, height))),
+        ..settings
+    };
+
+    let current_rect = Rect::new(
+        MARGIN as i32,
+        MARGIN as i32,
+        (width as f64 * CURRENT_SIZE) as u32,
+        (height as f64 * CURRENT_SIZE) as u32,
+    );
+    draw_slide(scene, canvas, current_rect, slide_settings);
+
+    let side_x = current_rect.right() + MARGIN as i32;
+    let side_width = width.saturating_sub(side_x as u32 + MARGIN);
+    let preview_rect = Rect::new(
+        side_x,
+        MARGIN as i32,
+        side_width,
+        (side_width as f64 * height as f64 / width as f64) as u32,
+    );
+    match preview {
+        Some(preview) if preview.action() == Action::Continue => {
+            draw_slide(preview, canvas, preview_rect, slide_settings);
+        }
+        _ => {
+            canvas.set_draw_color(BORDER_COLOR);
+            canvas.draw_rect(preview_rect).expect("Can't draw");
+        }
+    }
+
+    let clock_height = (height as f64 * CLOCK_SIZE) as u32;
+    let mut clock_y = preview_rect.bottom() + MARGIN as i32;
+
+    canvas.set_draw_color(CLOCK_COLOR);
+    let now = Local::now();
+    let wall_clock = format!("{:02}:{:02}", now.hour(), now.minute());
+    seven_segment(
+        canvas,
+        &wall_clock,
+        Point::new(side_x, clock_y),
+        clock_height,
+    );
+    clock_y += (clock_height + MARGIN) as i32;
+
+    let elapsed = format_seconds(timer.elapsed().as_secs() as i64);
+    seven_segment(canvas, &elapsed, Point::new(side_x, clock_y), clock_height);
+    clock_y += (clock_height + MARGIN) as i32;
+
+    if let Some(remaining) = timer.remaining() {
+        if remaining < 0 {
+            canvas.set_draw_color(OVERTIME_COLOR);
+        }
+        let countdown = format_seconds(remaining);
+        seven_segment(
+            canvas,
+            &countdown,
+            Point::new(side_x, clock_y),
+            clock_height,
+        );
+        clock_y += (clock_height + MARGIN) as i32;
+    }
+
+    if !goto_input.is_empty() {
+        canvas.set_draw_color(GOTO_COLOR);
+        seven_segment(
+            canvas,
+            goto_input,
+            Point::new(side_x, clock_y),
+            clock_height,
+        );
+    }
+
+    let notes_y = current_rect.bottom() + MARGIN as i32;
+    let notes_rect = Rect::new(
+        MARGIN as i32,
+        notes_y,
+        width.saturating_sub(2 * MARGIN),
+        height.saturating_sub(notes_y as u32 + MARGIN).max(1),
+    );
+    scene.draw_notes(canvas, &Position::Rect(notes_rect), settings);
+}
+
+/// Draw a scene scaled down into a rect, as if it was drawn in the entire window
+fn draw_slide<T: Scene + ?Sized>(
+    scene: &T,
+    canvas: &mut dyn DrawTarget,
+    rect: Rect,
+    settings: DrawSettings,
+) {
+    draw_scaled(canvas, rect, 1., |canvas| {
+        draw_scene(scene, canvas, settings);
+    });
+
+    canvas.set_draw_color(BORDER_COLOR);
+    canvas.draw_rect(rect).expect("Can't draw");
+}
+
+/// Format a number of seconds as `h:mm:ss`, or `mm:ss` if it's less than an hour
+fn format_seconds(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.abs();
+
+    if seconds >= 3600 {
+        format!(
+            "{}{}:{:02}:{:02}",
+            sign,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}{:02}:{:02}", sign, seconds / 60, seconds % 60)
+    }
+}
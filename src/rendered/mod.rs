@@ -0,0 +1,158 @@
+//! Render any object with a known size into an image, so that it can be used where an image is
+//! needed, such as in a [`Ditherer`]
+//!
+//! [`Ditherer`]: ../ditherer/struct.Ditherer.html
+
+use sdl2::rect::Rect;
+
+use drawable::{
+    offscreen_canvas, DrawSettings, DrawTarget, Drawable, KnownSize, Position, State,
+    DSETTINGS_MAIN,
+};
+use image::{ImageContainer, PngImage};
+
+/// A wrapper that renders its object into an image when loaded. The object itself is still drawn
+/// as usual, the image is only used by whatever wants an [`ImageContainer`], so a [`Ditherer`]
+/// can fade in entire compositions, such as a [`Stack`], together.
+///
+/// The image is rendered when loaded, and again whenever the object changes size, such as a
+/// [`LatexObj`] that is rendered in the background. Other changes aren't in the image. The object
+/// is rendered as it looks after loading, so objects that are hidden until they're stepped, such
+/// as a [`Ditherer`] that hasn't dithered in yet, are missing from it.
+///
+/// ```
+/// use ytesrev::prelude::*;
+/// use ytesrev::rendered::Rendered;
+///
+/// let square = WithSize::new((100, 100), Solid::new_rgba(255, 0, 0, 255));
+/// let ditherer = Ditherer::new(Rendered::new(square));
+/// ```
+///
+/// [`ImageContainer`]: ../image/trait.ImageContainer.html
+/// [`Ditherer`]: ../ditherer/struct.Ditherer.html
+/// [`Stack`]: ../layout/stack/struct.Stack.html
+/// [`LatexObj`]: ../latex/latex_obj/struct.LatexObj.html
+pub struct Rendered<T: KnownSize> {
+    /// The inner object
+    pub inner: T,
+    /// The rendered image, empty until loaded
+    pub image: PngImage,
+    /// The size of the inner object when it was last rendered
+    rendered_size: (usize, usize),
+}
+
+impl<T: KnownSize> Rendered<T> {
+    /// Create a new Rendered wrapper
+    pub fn new(inner: T) -> Rendered<T> {
+        Rendered {
+            inner,
+            image: PngImage::new(0, 0, Vec::new()),
+            rendered_size: (0, 0),
+        }
+    }
+
+    /// Render the inner object into the image again, at its current size
+    fn rerender(&mut self) {
+        self.rendered_size = (self.inner.width(), self.inner.height());
+
+        match self.render() {
+            Ok(image) => {
+                self.image = image;
+            }
+            Err(e) => {
+                eprintln!("Couldn't render object into an image: {}", e);
+            }
+        }
+    }
+
+    fn render(&self) -> Result<PngImage, String> {
+        let (width, height) = (self.inner.width() as u32, self.inner.height() as u32);
+        if width == 0 || height == 0 {
+            // Nothing to render yet
+            return Ok(PngImage::new(0, 0, Vec::new()));
+        }
+
+        let mut canvas = offscreen_canvas(width, height)?;
+        self.inner.draw(
+            &mut canvas,
+            &Position::Rect(Rect::new(0, 0, width, height)),
+            DSETTINGS_MAIN,
+        );
+        let mut image = PngImage::from_canvas(&canvas)?;
+
+        // Blending onto the transparent canvas multiplies the colors with the alpha
+        for pixel in image.data.chunks_mut(4) {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel[0..3] {
+                if let Some(straight) = (*channel as u32 * 255).checked_div(alpha) {
+                    *channel = straight.min(255) as u8;
+                }
+            }
+        }
+
+        Ok(image)
+    }
+}
+
+impl<T: KnownSize> Drawable for Rendered<T> {
+    fn content(&self) -> Vec<&dyn Drawable> {
+        vec![&self.inner]
+    }
+
+    fn content_mut(&mut self) -> Vec<&mut dyn Drawable> {
+        vec![&mut self.inner]
+    }
+
+    fn load(&mut self) {
+        self.inner.load();
+        self.rerender();
+    }
+
+    fn update(&mut self, dt: f64) {
+        self.inner.update(dt);
+
+        if (self.inner.width(), self.inner.height()) != self.rendered_size {
+            self.rerender();
+        }
+    }
+
+    fn step(&mut self) {
+        self.inner.step()
+    }
+
+    fn step_back(&mut self) {
+        self.inner.step_back()
+    }
+
+    fn state(&self) -> State {
+        self.inner.state()
+    }
+
+    fn draw(&self, canvas: &mut dyn DrawTarget, position: &Position, settings: DrawSettings) {
+        // Drawn where the image would be, so that it doesn't move when it's used instead
+        let rect = position.into_rect_with_size(self.width() as u32, self.height() as u32);
+        self.inner.draw(canvas, &Position::Rect(rect), settings);
+    }
+}
+
+impl<T: KnownSize> KnownSize for Rendered<T> {
+    fn width(&self) -> usize {
+        self.image.width
+    }
+
+    fn height(&self) -> usize {
+        self.image.height
+    }
+}
+
+impl<T: KnownSize> ImageContainer for Rendered<T> {
+    fn get_data(&self) -> &Vec<u8> {
+        &self.image.data
+    }
+    fn get_data_mut(&mut self) -> &mut Vec<u8> {
+        self.image.get_data_mut()
+    }
+    fn into_data(self) -> Vec<u8> {
+        self.image.data
+    }
+}
@@ -0,0 +1,200 @@
+//! Scale objects with a known size, such as images, up or down
+
+use sdl2::rect::Rect;
+
+use drawable::{
+    draw_scaled, draw_stretched, fit_into, DrawSettings, DrawTarget, Drawable, KnownSize, Position,
+    State,
+};
+
+/// How a [`ScaleToFit`] should fit its object into the space it's given
+///
+/// [`ScaleToFit`]: struct.ScaleToFit.html
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ScaleMode {
+    /// Make the object as large as possible while still fitting entirely, keeping the aspect
+    /// ratio. The object is centered, with empty space on two of the sides.
+    Contain,
+    /// Make the object as small as possible while still covering the entire space, keeping the
+    /// aspect ratio. The object is centered and cut off on two of the sides.
+    Cover,
+    /// Fill the entire space, ignoring the aspect ratio
+    Stretch,
+}
+
+/// A wrapper that scales its object to fit the rect it's drawn in, such as a pane of a
+/// [`Split`]. When not drawn in a rect, the object is drawn in its own size.
+///
+/// ```
+/// use ytesrev::prelude::*;
+/// use ytesrev::scale::{ScaleMode, ScaleToFit};
+///
+/// let image = PngImage::new(300, 200, vec![0; 4 * 300 * 200]);
+/// let image = ScaleToFit::new(ScaleMode::Contain, image);
+/// assert_eq!(image.width(), 300);
+/// ```
+///
+/// [`Split`]: ../layout/split/struct.Split.html
+pub struct ScaleToFit<T: KnownSize> {
+    /// The inner object
+    pub inner: T,
+    /// How to fit the object
+    pub mode: ScaleMode,
+}
+
+impl<T: KnownSize> ScaleToFit<T> {
+    /// Create a new ScaleToFit wrapper
+    pub fn new(mode: ScaleMode, inner: T) -> ScaleToFit<T> {
+        ScaleToFit { inner, mode }
+    }
+}
+
+impl<T: KnownSize> Drawable for ScaleToFit<T> {
+    fn content(&self) -> Vec<&dyn Drawable> {
+        vec![&self.inner]
+    }
+
+    fn content_mut(&mut self) -> Vec<&mut dyn Drawable> {
+        vec![&mut self.inner]
+    }
+
+    fn step(&mut self) {
+        self.inner.step()
+    }
+
+    fn step_back(&mut self) {
+        self.inner.step_back()
+    }
+
+    fn state(&self) -> State {
+        self.inner.state()
+    }
+
+    fn draw(&self, canvas: &mut dyn DrawTarget, position: &Position, settings: DrawSettings) {
+        let size = (self.inner.width() as u32, self.inner.height() as u32);
+        let rect = match *position {
+            Position::Rect(rect) => rect,
+            _ => {
+                self.inner.draw(canvas, position, settings);
+                return;
+            }
+        };
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+
+        let inner_rect = Rect::new(0, 0, size.0, size.1);
+        match self.mode {
+            ScaleMode::Contain => {
+                let (scale, fitted) = fit_into(size, rect);
+                draw_scaled(canvas, fitted, scale, |canvas| {
+                    self.inner
+                        .draw(canvas, &Position::Rect(inner_rect), settings);
+                });
+            }
+            ScaleMode::Cover => {
+                let scale =
+                    (rect.width() as f64 / size.0 as f64).max(rect.height() as f64 / size.1 as f64);
+
+                // Center the object, with the overflow cut off by the viewport
+                let x = (rect.width() as f64 / scale - size.0 as f64) / 2.;
+                let y = (rect.height() as f64 / scale - size.1 as f64) / 2.;
+                let centered = Rect::new(x as i32, y as i32, size.0, size.1);
+
+                draw_scaled(canvas, rect, scale, |canvas| {
+                    self.inner.draw(canvas, &Position::Rect(centered), settings);
+                });
+            }
+            ScaleMode::Stretch => {
+                let scale = (
+                    rect.width() as f64 / size.0 as f64,
+                    rect.height() as f64 / size.1 as f64,
+                );
+                draw_stretched(canvas, rect, scale, |canvas| {
+                    self.inner
+                        .draw(canvas, &Position::Rect(inner_rect), settings);
+                });
+            }
+        }
+    }
+}
+
+impl<T: KnownSize> KnownSize for ScaleToFit<T> {
+    fn width(&self) -> usize {
+        self.inner.width()
+    }
+
+    fn height(&self) -> usize {
+        self.inner.height()
+    }
+}
+
+/// A wrapper that scales its object by a constant factor
+///
+/// ```
+/// use ytesrev::prelude::*;
+/// use ytesrev::scale::Scale;
+///
+/// let image = PngImage::new(300, 200, vec![0; 4 * 300 * 200]);
+/// let image = Scale::new(0.5, image);
+/// assert_eq!((image.width(), image.height()), (150, 100));
+/// ```
+pub struct Scale<T: KnownSize> {
+    /// The inner object
+    pub inner: T,
+    /// How much to scale the object by
+    pub factor: f64,
+}
+
+impl<T: KnownSize> Scale<T> {
+    /// Create a new Scale wrapper
+    pub fn new(factor: f64, inner: T) -> Scale<T> {
+        Scale { inner, factor }
+    }
+}
+
+impl<T: KnownSize> Drawable for Scale<T> {
+    fn content(&self) -> Vec<&dyn Drawable> {
+        vec![&self.inner]
+    }
+
+    fn content_mut(&mut self) -> Vec<&mut dyn Drawable> {
+        vec![&mut self.inner]
+    }
+
+    fn step(&mut self) {
+        self.inner.step()
+    }
+
+    fn step_back(&mut self) {
+        self.inner.step_back()
+    }
+
+    fn state(&self) -> State {
+        self.inner.state()
+    }
+
+    fn draw(&self, canvas: &mut dyn DrawTarget, position: &Position, settings: DrawSettings) {
+        if self.factor <= 0. {
+            return;
+        }
+
+        let rect = position.into_rect_with_size(self.width() as u32, self.height() as u32);
+        let inner_rect = Rect::new(0, 0, self.inner.width() as u32, self.inner.height() as u32);
+
+        draw_scaled(canvas, rect, self.factor, |canvas| {
+            self.inner
+                .draw(canvas, &Position::Rect(inner_rect), settings);
+        });
+    }
+}
+
+impl<T: KnownSize> KnownSize for Scale<T> {
+    fn width(&self) -> usize {
+        (self.inner.width() as f64 * self.factor) as usize
+    }
+
+    fn height(&self) -> usize {
+        (self.inner.height() as f64 * self.factor) as usize
+    }
+}
@@ -8,11 +8,11 @@ use std::thread::spawn;
 
 use rayon::scope;
 
+use sdl2::pixels::Color;
 use sdl2::rect::Rect;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
 
-use drawable::{DrawSettings, Drawable, Position, State};
+use drawable::{draw_scaled, DrawSettings, DrawTarget, Drawable, Position, State};
+use notes::{draw_notes, Note};
 use window::YEvent;
 
 /// An action that allows the [`Scene`] to communicate with the [`WindowManager`]
@@ -35,8 +35,10 @@ pub enum Action {
 pub trait Scene: Send {
     /// Do a tick
     fn update(&mut self, _dt: f64);
-    /// Draw the content of this scene to a `Canvas`.
-    fn draw(&self, canvas: &mut Canvas<Window>, settings: DrawSettings);
+    /// Draw the content of this scene to a [`DrawTarget`], such as a window's `Canvas`.
+    ///
+    /// [`DrawTarget`]: ../drawable/trait.DrawTarget.html
+    fn draw(&self, canvas: &mut dyn DrawTarget, settings: DrawSettings);
     /// Called when an event occured
     fn event(&mut self, _event: YEvent);
     /// What to do
@@ -49,6 +51,40 @@ pub trait Scene: Send {
     ///
     /// [`Drawable::load`]: ../drawable/struct.Drawable.html#method.register
     fn load(&mut self);
+    /// The index of the slide currently being shown, for scenes containing multiple slides such
+    /// as a [`SceneList`]
+    fn slide(&self) -> usize {
+        0
+    }
+    /// How many times the current slide has been stepped, see [`Scene::slide`]
+    fn slide_step(&self) -> usize {
+        0
+    }
+    /// The speaker notes for what is currently being shown
+    fn notes(&self) -> Vec<&Note> {
+        Vec::new()
+    }
+    /// Draw the speaker notes for what is currently being shown. This is called by the presenter
+    /// view in windows with `notes_view` set, and draws nothing by default.
+    fn draw_notes(
+        &self,
+        _canvas: &mut dyn DrawTarget,
+        _position: &Position,
+        _settings: DrawSettings,
+    ) {
+    }
+    /// Is an overview of the slides being shown in the notes window? While it's shown, clicks in
+    /// the notes window are sent as [`YEvent::OverviewClick`] instead of stepping. Scenes without
+    /// an overview never show one.
+    ///
+    /// [`YEvent::OverviewClick`]: ../window/enum.YEvent.html#variant.OverviewClick
+    fn is_overview_shown(&self) -> bool {
+        false
+    }
+    /// Skip all ongoing animations. The scene equivalent of [`Drawable::skip_animation`]
+    ///
+    /// [`Drawable::skip_animation`]: ../drawable/trait.Drawable.html#method.skip_animation
+    fn skip_animation(&mut self) {}
 }
 
 /// A wrapper to make a [`Drawable`] into a [`Scene`]. This is probably all you will need
@@ -62,21 +98,29 @@ impl<T: Drawable> Scene for DrawableWrapper<T> {
         self.0.update(dt);
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, settings: DrawSettings) {
-        let (w, h) = canvas.window().size();
-        self.0
-            .draw(canvas, &Position::Rect(Rect::new(0, 0, w, h)), settings);
+    fn draw(&self, canvas: &mut dyn DrawTarget, settings: DrawSettings) {
+        let viewport = canvas.viewport();
+        let rect = Rect::new(0, 0, viewport.width(), viewport.height());
+        self.0.draw(canvas, &Position::Rect(rect), settings);
     }
 
     fn event(&mut self, event: YEvent) {
         match event {
             YEvent::Step => {
+                self.0.before_step();
                 self.0.step();
             }
+            YEvent::StepBack => {
+                self.0.step_back();
+            }
             YEvent::Other(e) => {
                 self.0.event(e);
             }
-            YEvent::StepSlide => {}
+            YEvent::StepSlide
+            | YEvent::StepSlideBack
+            | YEvent::Goto(..)
+            | YEvent::ToggleOverview
+            | YEvent::OverviewClick { .. } => {}
         }
     }
 
@@ -94,24 +138,112 @@ impl<T: Drawable> Scene for DrawableWrapper<T> {
     fn load(&mut self) {
         self.0.load()
     }
+    fn notes(&self) -> Vec<&Note> {
+        self.0.notes()
+    }
+    fn draw_notes(&self, canvas: &mut dyn DrawTarget, position: &Position, settings: DrawSettings) {
+        draw_notes(&self.0.notes(), canvas, position, settings);
+    }
+    fn skip_animation(&mut self) {
+        self.0.skip_animation()
+    }
 }
 
+/// How much of each cell in the overview a thumbnail takes up
+const OVERVIEW_THUMBNAIL_SIZE: f64 = 0.9;
+
 /// A list of scenes that are showed in order. When the current scene's action is [`Action::Done`]
 /// the next scene is loaded.
+///
+/// In windows with `notes_view` set, the list can also show an overview, a grid of thumbnails of
+/// every scene, toggled by [`YEvent::ToggleOverview`]. Clicking a thumbnail jumps to that scene.
+///
+/// [`YEvent::ToggleOverview`]: ../window/enum.YEvent.html#variant.ToggleOverview
 pub struct SceneList {
     /// The list of scenes
     pub scenes: Vec<Box<dyn Scene>>,
 
-    /// The index of the current scene being showed. Garuanteed to always be in bounds
+    /// The index of the current scene being showed. Equal to the number of scenes when the list
+    /// is done
     current_scene: usize,
+
+    /// How many times each scene has been stepped, used by [`SceneList::goto`]
+    steps: Vec<usize>,
+
+    /// Is the overview being shown?
+    overview: bool,
 }
 
 impl SceneList {
     /// Create a new SceneList
     pub fn new(scenes: Vec<Box<dyn Scene>>) -> SceneList {
         SceneList {
+            steps: vec![0; scenes.len()],
             scenes,
             current_scene: 0,
+            overview: false,
+        }
+    }
+
+    /// Where the thumbnail of each scene is in the overview, for a target of a specific size.
+    /// Returns the scale of the thumbnails, and their rects in order.
+    pub fn overview_layout(&self, size: (u32, u32)) -> (f64, Vec<Rect>) {
+        let nscenes = self.scenes.len().max(1);
+        let cols = (nscenes as f64).sqrt().ceil() as usize;
+        let rows = nscenes.div_ceil(cols);
+
+        let cell_width = size.0 as f64 / cols as f64;
+        let cell_height = size.1 as f64 / rows as f64;
+
+        // Every thumbnail has the same aspect ratio as the target
+        let scale =
+            (cell_width / size.0 as f64).min(cell_height / size.1 as f64) * OVERVIEW_THUMBNAIL_SIZE;
+        let width = size.0 as f64 * scale;
+        let height = size.1 as f64 * scale;
+
+        let rects = (0..self.scenes.len())
+            .map(|i| {
+                let col = (i % cols) as f64;
+                let row = (i / cols) as f64;
+                Rect::new(
+                    (cell_width * col + (cell_width - width) / 2.) as i32,
+                    (cell_height * row + (cell_height - height) / 2.) as i32,
+                    width as u32,
+                    height as u32,
+                )
+            })
+            .collect();
+
+        (scale, rects)
+    }
+
+    fn draw_overview(&self, canvas: &mut dyn DrawTarget, settings: DrawSettings) {
+        let viewport = canvas.viewport();
+        let size = (viewport.width(), viewport.height());
+        let (scale, rects) = self.overview_layout(size);
+
+        // The thumbnails should look like the main window, without the debug outlines
+        let thumbnail_settings = DrawSettings {
+            notes_view: false,
+            ..settings
+        };
+        let (r, g, b) = settings.background_color;
+
+        for (i, (scene, rect)) in self.scenes.iter().zip(rects).enumerate() {
+            draw_scaled(canvas, rect, scale, |canvas| {
+                canvas.set_draw_color(Color::RGB(r, g, b));
+                canvas
+                    .fill_rect(Rect::new(0, 0, size.0, size.1))
+                    .expect("Can't draw");
+                scene.draw(canvas, thumbnail_settings);
+            });
+
+            if i == self.current_scene {
+                canvas.set_draw_color(Color::RGB(255, 0, 0));
+            } else {
+                canvas.set_draw_color(Color::RGB(128, 128, 128));
+            }
+            canvas.draw_rect(rect).expect("Can't draw");
         }
     }
 
@@ -119,24 +251,106 @@ impl SceneList {
     pub fn get_current_scene(&self) -> usize {
         self.current_scene
     }
+
+    /// Gets how many times the current scene has been stepped
+    pub fn get_current_step(&self) -> usize {
+        self.steps.get(self.current_scene).cloned().unwrap_or(0)
+    }
+
+    /// Jump directly to a specific step in a specific scene, skipping all animations on the way.
+    /// Both indices start at zero, and the step is how many times the scene has been stepped. If
+    /// the scene is after the last one, the last scene is used.
+    pub fn goto(&mut self, scene: usize, step: usize) {
+        if self.scenes.is_empty() {
+            return;
+        }
+        self.current_scene = scene.min(self.scenes.len() - 1);
+
+        while self.get_current_step() > step {
+            self.step_current(YEvent::StepBack);
+        }
+        while self.get_current_step() < step && self.current().action() == Action::Continue {
+            self.step_current(YEvent::Step);
+            self.current().skip_animation();
+        }
+        self.current().skip_animation();
+    }
+
+    fn current(&mut self) -> &mut Box<dyn Scene> {
+        &mut self.scenes[self.current_scene]
+    }
+
+    /// Send a `Step` or `StepBack` to the current scene, keeping track of the step count
+    fn step_current(&mut self, event: YEvent) {
+        if self.steps.len() < self.scenes.len() {
+            self.steps.resize(self.scenes.len(), 0);
+        }
+
+        match event {
+            YEvent::Step => self.steps[self.current_scene] += 1,
+            YEvent::StepBack => {
+                self.steps[self.current_scene] = self.steps[self.current_scene].saturating_sub(1)
+            }
+            _ => {}
+        }
+
+        self.current().event(event);
+    }
 }
 
 impl Scene for SceneList {
     fn update(&mut self, dt: f64) {
-        self.scenes[self.current_scene].update(dt);
+        if let Some(scene) = self.scenes.get_mut(self.current_scene) {
+            scene.update(dt);
 
-        if self.scenes[self.current_scene].action() == Action::Done {
-            self.current_scene += 1;
+            if scene.action() == Action::Done {
+                self.current_scene += 1;
+            }
         }
     }
-    fn draw(&self, canvas: &mut Canvas<Window>, settings: DrawSettings) {
-        self.scenes[self.current_scene].draw(canvas, settings);
+    fn draw(&self, canvas: &mut dyn DrawTarget, settings: DrawSettings) {
+        if self.overview && settings.notes_view {
+            self.draw_overview(canvas, settings);
+        } else if let Some(scene) = self.scenes.get(self.current_scene) {
+            scene.draw(canvas, settings);
+        }
     }
     fn event(&mut self, event: YEvent) {
         match event {
-            YEvent::StepSlide => self.current_scene += 1,
+            YEvent::StepSlide => {
+                self.current_scene = (self.current_scene + 1).min(self.scenes.len())
+            }
+            YEvent::StepSlideBack => {
+                if self.current_scene > 0 {
+                    self.current_scene -= 1;
+
+                    // The previous scene is usually done, so step back to its final state to
+                    // keep it from being skipped again
+                    if self.current().action() == Action::Done {
+                        self.step_current(YEvent::StepBack);
+                    }
+                }
+            }
+            YEvent::Goto(scene, step) => self.goto(scene, step),
+            YEvent::ToggleOverview => self.overview = !self.overview,
+            YEvent::OverviewClick { x, y, size } => {
+                if self.overview {
+                    let (_, rects) = self.overview_layout(size);
+                    if let Some(idx) = rects.iter().position(|rect| rect.contains_point((x, y))) {
+                        self.goto(idx, 0);
+                        self.overview = false;
+                    }
+                }
+            }
+            YEvent::Step | YEvent::StepBack => {
+                if self.current_scene < self.scenes.len() {
+                    self.step_current(event);
+                }
+            }
             _ => {
-                self.scenes[self.current_scene].event(event);
+                if let Some(scene) = self.scenes.get_mut(self.current_scene) {
+                    scene.event(event);
+                }
             }
         }
     }
@@ -187,6 +401,37 @@ impl Scene for SceneList {
             }
         });
     }
+
+    fn slide(&self) -> usize {
+        self.current_scene
+    }
+
+    fn slide_step(&self) -> usize {
+        self.get_current_step()
+    }
+
+    fn notes(&self) -> Vec<&Note> {
+        match self.scenes.get(self.current_scene) {
+            Some(scene) => scene.notes(),
+            None => Vec::new(),
+        }
+    }
+
+    fn draw_notes(&self, canvas: &mut dyn DrawTarget, position: &Position, settings: DrawSettings) {
+        if let Some(scene) = self.scenes.get(self.current_scene) {
+            scene.draw_notes(canvas, position, settings);
+        }
+    }
+
+    fn is_overview_shown(&self) -> bool {
+        self.overview
+    }
+
+    fn skip_animation(&mut self) {
+        if let Some(scene) = self.scenes.get_mut(self.current_scene) {
+            scene.skip_animation();
+        }
+    }
 }
 
 fn print_state(statuses: &[u8]) {
@@ -200,3 +445,137 @@ fn print_state(statuses: &[u8]) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scene that is done once it's stepped past its last step
+    struct Steps {
+        step: usize,
+        last: usize,
+    }
+
+    impl Scene for Steps {
+        fn update(&mut self, _dt: f64) {}
+        fn draw(&self, _canvas: &mut dyn DrawTarget, _settings: DrawSettings) {}
+        fn event(&mut self, event: YEvent) {
+            match event {
+                YEvent::Step => self.step += 1,
+                YEvent::StepBack => self.step = self.step.saturating_sub(1),
+                _ => {}
+            }
+        }
+        fn action(&self) -> Action {
+            if self.step > self.last {
+                Action::Done
+            } else {
+                Action::Continue
+            }
+        }
+        fn register(&mut self) {}
+        fn load(&mut self) {}
+    }
+
+    fn scene_list(lasts: &[usize]) -> SceneList {
+        SceneList::new(
+            lasts
+                .iter()
+                .map(|&last| Box::new(Steps { step: 0, last }) as Box<dyn Scene>)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn step_back_undoes_steps() {
+        let mut list = scene_list(&[2, 1]);
+        list.event(YEvent::Step);
+        list.event(YEvent::Step);
+        assert_eq!(list.get_current_step(), 2);
+
+        list.event(YEvent::StepBack);
+        assert_eq!(list.get_current_step(), 1);
+
+        // Stepping back can't go past the start of the slide
+        for _ in 0..3 {
+            list.event(YEvent::StepBack);
+        }
+        assert_eq!((list.get_current_scene(), list.get_current_step()), (0, 0));
+    }
+
+    #[test]
+    fn step_slide_back_returns_to_the_last_step() {
+        let mut list = scene_list(&[1, 1]);
+        list.event(YEvent::Step);
+        list.event(YEvent::Step);
+        list.update(0.);
+        assert_eq!(list.get_current_scene(), 1);
+
+        list.event(YEvent::StepSlideBack);
+        assert_eq!((list.get_current_scene(), list.get_current_step()), (0, 1));
+        assert!(list.action() == Action::Continue);
+
+        // There's no slide before the first one
+        list.event(YEvent::StepSlideBack);
+        assert_eq!(list.get_current_scene(), 0);
+    }
+
+    #[test]
+    fn goto_stays_within_the_slides() {
+        let mut list = scene_list(&[2, 1, 3]);
+
+        list.goto(10, 1);
+        assert_eq!((list.get_current_scene(), list.get_current_step()), (2, 1));
+
+        // Stepping stops once the slide is done
+        list.goto(0, 10);
+        assert_eq!((list.get_current_scene(), list.get_current_step()), (0, 3));
+
+        list.goto(0, 1);
+        assert_eq!((list.get_current_scene(), list.get_current_step()), (0, 1));
+        assert!(list.action() == Action::Continue);
+
+        let mut empty = scene_list(&[]);
+        empty.goto(1, 1);
+        assert!(empty.action() == Action::Done);
+    }
+
+    #[test]
+    fn overview_is_a_grid_of_thumbnails() {
+        let list = scene_list(&[0, 0, 0]);
+        let (scale, rects) = list.overview_layout((200, 100));
+
+        assert_eq!(scale, 0.5 * OVERVIEW_THUMBNAIL_SIZE);
+        assert_eq!(
+            rects,
+            vec![
+                Rect::new(5, 2, 90, 45),
+                Rect::new(105, 2, 90, 45),
+                Rect::new(5, 52, 90, 45),
+            ]
+        );
+    }
+
+    #[test]
+    fn clicking_a_thumbnail_goes_to_its_slide() {
+        let mut list = scene_list(&[0, 0, 0, 0]);
+        let click = |x, y| YEvent::OverviewClick {
+            x,
+            y,
+            size: (200, 100),
+        };
+
+        // Clicks are ignored while the overview is hidden
+        list.event(click(150, 75));
+        assert_eq!(list.get_current_scene(), 0);
+
+        list.event(YEvent::ToggleOverview);
+        list.event(click(2, 1));
+        assert_eq!(list.get_current_scene(), 0);
+        assert!(list.is_overview_shown());
+
+        list.event(click(150, 75));
+        assert_eq!(list.get_current_scene(), 3);
+        assert!(!list.is_overview_shown());
+    }
+}
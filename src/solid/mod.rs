@@ -1,10 +1,8 @@
 //! A solid square of color
 
 use sdl2::pixels::Color;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
 
-use drawable::{DrawSettings, Drawable, Position, State};
+use drawable::{DrawSettings, DrawTarget, Drawable, Position, State};
 
 /// A solid, fillid with a color
 pub struct Solid {
@@ -40,6 +38,10 @@ impl Drawable for Solid {
         self.shown = false;
     }
 
+    fn step_back(&mut self) {
+        self.shown = true;
+    }
+
     fn state(&self) -> State {
         if self.shown {
             State::Final
@@ -48,7 +50,7 @@ impl Drawable for Solid {
         }
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, position: &Position, _settings: DrawSettings) {
+    fn draw(&self, canvas: &mut dyn DrawTarget, position: &Position, _settings: DrawSettings) {
         match position {
             Position::Rect(r) => {
                 canvas.set_draw_color(self.color);
@@ -2,25 +2,26 @@
 
 use std::mem;
 
-use sdl2::rect::Point;
-use sdl2::render::{BlendMode, Canvas};
-use sdl2::video::Window;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::BlendMode;
+
+use drawable::DrawTarget;
 
 const EPSILON: f64 = 0.0001;
 
 /// Draw an antialiased line. Uses circles as caps
-pub fn line_aa(canvas: &mut Canvas<Window>, start: (f64, f64), end: (f64, f64)) {
+pub fn line_aa(canvas: &mut dyn DrawTarget, start: (f64, f64), end: (f64, f64)) {
     line_aa_width(canvas, start, end, 1.);
 }
 
 /// Draw an antialiased circle.
-pub fn circle_aa(canvas: &mut Canvas<Window>, center: (f64, f64), rad: f64) {
+pub fn circle_aa(canvas: &mut dyn DrawTarget, center: (f64, f64), rad: f64) {
     line_aa_width(canvas, center, center, rad);
 }
 
 /// Draw an antialiased line with a specified line width
 pub fn line_aa_width(
-    canvas: &mut Canvas<Window>,
+    canvas: &mut dyn DrawTarget,
     mut start: (f64, f64),
     mut end: (f64, f64),
     line_size: f64,
@@ -122,7 +123,7 @@ pub fn line_aa_width(
     }
 }
 
-fn put_pixel(canvas: &mut Canvas<Window>, at: (f64, f64), intensity: f64, steep: bool) {
+fn put_pixel(canvas: &mut dyn DrawTarget, at: (f64, f64), intensity: f64, steep: bool) {
     let color_orig = canvas.draw_color();
     let mut color = color_orig.clone();
 
@@ -151,3 +152,61 @@ fn fpart(x: f64) -> f64 {
 fn rfpart(x: f64) -> f64 {
     1. - fpart(x)
 }
+
+/// Which of the seven segments are lit for each digit, in the order top, top right, bottom right,
+/// bottom, bottom left, top left and middle
+const SEGMENTS: [u8; 10] = [63, 6, 91, 79, 102, 109, 125, 7, 127, 111];
+
+/// Draw a string of digits, `:`, `.` and `-` as seven segment digits, with the top left corner at a
+/// specific point. Other characters are drawn as spaces. Returns the width of what was drawn.
+pub fn seven_segment(canvas: &mut dyn DrawTarget, text: &str, corner: Point, height: u32) -> u32 {
+    let width = height / 2;
+    let thickness = (height / 10).max(1);
+    let half = height / 2;
+
+    let mut x = corner.x();
+    let y = corner.y();
+
+    for ch in text.chars() {
+        let segments = match ch {
+            '0'..='9' => SEGMENTS[ch as usize - '0' as usize],
+            '-' => 64,
+            ':' => {
+                for dot_y in &[y + height as i32 / 3, y + 2 * height as i32 / 3] {
+                    let dot = Rect::new(x, *dot_y, thickness, thickness);
+                    canvas.fill_rect(dot).expect("Can't draw");
+                }
+                x += 3 * thickness as i32;
+                continue;
+            }
+            '.' => {
+                let dot = Rect::new(x, y + (height - thickness) as i32, thickness, thickness);
+                canvas.fill_rect(dot).expect("Can't draw");
+                x += 3 * thickness as i32;
+                continue;
+            }
+            _ => 0,
+        };
+
+        let right = x + (width - thickness) as i32;
+        let rects = [
+            Rect::new(x, y, width, thickness),
+            Rect::new(right, y, thickness, half),
+            Rect::new(right, y + half as i32, thickness, half),
+            Rect::new(x, y + (height - thickness) as i32, width, thickness),
+            Rect::new(x, y + half as i32, thickness, half),
+            Rect::new(x, y, thickness, half),
+            Rect::new(x, y + ((height - thickness) / 2) as i32, width, thickness),
+        ];
+
+        for (i, rect) in rects.iter().enumerate() {
+            if segments & (1 << i) != 0 {
+                canvas.fill_rect(*rect).expect("Can't draw");
+            }
+        }
+
+        x += (width + 2 * thickness) as i32;
+    }
+
+    (x - corner.x()) as u32
+}
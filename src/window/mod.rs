@@ -3,28 +3,73 @@
 extern crate sdl2;
 
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, LSHIFTMOD, RSHIFTMOD};
 use sdl2::pixels::Color;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
+use sdl2::rect::Rect;
+use sdl2::video::{FullscreenType, Window};
 use sdl2::EventPump;
 use sdl2::Sdl;
 
+use std::io::Result as IResult;
+use std::path::{Path, PathBuf};
 use std::thread::sleep;
 use std::time::{Duration, Instant};
 
-use drawable::{DrawSettings, DSETTINGS_MAIN, DSETTINGS_NOTES};
-use latex::render::render_all_equations;
+use diskcache::{default_cache_dir, set_cache_dir};
+use ditherer::set_default_seed;
+use drawable::{
+    draw_scaled, fit_into, CachedCanvas, DrawSettings, DrawTarget, DSETTINGS_MAIN, DSETTINGS_NOTES,
+};
+use export::handout::{export_handout, HandoutSettings, HSETTINGS_MAIN};
+use export::video::{record_with, RecordSettings};
+use export::{export_steps, ExportSettings, ESETTINGS_MAIN};
+use latex::backend::LatexBackend;
+use latex::render::{render_all_equations, set_backend};
+use presenter::{draw_presenter, TalkTimer};
 use scene::{Action, Scene};
 
 const FPS_PRINT_RATE: Duration = Duration::from_millis(1000);
+/// How long an entered slide is kept, without anything more being typed, before it's forgotten
+const GOTO_TIMEOUT: Duration = Duration::from_millis(3000);
+
+/// The color of the bars around the scene when the window has another aspect ratio than it
+const LETTERBOX_COLOR: Color = Color {
+    r: 0,
+    g: 0,
+    b: 0,
+    a: 255,
+};
 
 /// An event. Passed into the `Drawable::event` and `Scene::event` functions
+#[derive(Clone)]
 pub enum YEvent {
     /// A special event that is emmitted when the user advances the state of the presentation
     Step,
     /// Step to the next slide in the presentation
     StepSlide,
+    /// Undo the last step, see [`Drawable::step_back`]
+    ///
+    /// [`Drawable::step_back`]: ../drawable/trait.Drawable.html#method.step_back
+    StepBack,
+    /// Go back to the previous slide in the presentation
+    StepSlideBack,
+    /// Jump to a specific slide and step, see [`SceneList::goto`]
+    ///
+    /// [`SceneList::goto`]: ../scene/struct.SceneList.html#method.goto
+    Goto(usize, usize),
+    /// Show or hide the overview of all slides in the notes window, see [`SceneList`]
+    ///
+    /// [`SceneList`]: ../scene/struct.SceneList.html
+    ToggleOverview,
+    /// A click in the notes window while the overview is shown
+    OverviewClick {
+        /// The x coordinate of the click, in the units the scene is drawn in
+        x: i32,
+        /// The y coordinate of the click, in the units the scene is drawn in
+        y: i32,
+        /// The size of the area the scene is drawn in, in the same units
+        size: (u32, u32),
+    },
     /// Anything else
     Other(Event),
 }
@@ -35,6 +80,17 @@ pub struct WindowSettings {
     pub draw_settings: DrawSettings,
     /// The size of this window
     pub window_size: (u32, u32),
+    /// The index of the display to open this window on, such as a projector. If there's no such
+    /// display, the first one is used.
+    pub display: i32,
+    /// Should this window be fullscreen? `FullscreenType::Desktop` keeps the resolution of the
+    /// display, `FullscreenType::True` changes it to the size of the window.
+    pub fullscreen: FullscreenType,
+    /// Should this window be without borders and title bar?
+    pub borderless: bool,
+    /// Where to put the top left corner of the window, relative to the top left corner of the
+    /// display. `None` centers the window on the display.
+    pub position: Option<(i32, i32)>,
 }
 
 /// Settings for the manager in general
@@ -46,8 +102,42 @@ pub struct WindowManagerSettings {
     pub event_step_rule: Box<dyn Fn(Event) -> bool>,
     /// What events should make the presentation go forward one slide? Default: enter
     pub event_step_slide_rule: Box<dyn Fn(Event) -> bool>,
+    /// What events should undo the last step? Default: backspace
+    pub event_step_back_rule: Box<dyn Fn(Event) -> bool>,
+    /// What events should make the presentation go back one slide? Default: shift + backspace
+    pub event_step_slide_back_rule: Box<dyn Fn(Event) -> bool>,
+    /// What character, if any, does an event type when entering a slide to go to? Slides are
+    /// entered as `slide` or `slide.step`, starting from 1 and 0 respectively. What has been
+    /// entered is shown in the presenter view, and forgotten when anything else is pressed, or
+    /// when nothing more is typed for a few seconds. Default: the number keys and period
+    pub goto_input_rule: Box<dyn Fn(Event) -> Option<char>>,
+    /// What events should go to the entered slide? Only used when a slide has been entered.
+    /// Default: enter
+    pub goto_rule: Box<dyn Fn(Event) -> bool>,
+    /// What events should show or hide the overview of all slides in the notes window? While
+    /// it's shown, clicking in the notes window jumps to the clicked slide instead of stepping.
+    /// Default: tab
+    pub overview_rule: Box<dyn Fn(Event) -> bool>,
     /// At what event should the presentation quit? Default: On escape or the window is closed.
     pub quit_rule: Box<dyn Fn(Event) -> bool>,
+    /// How long the talk should be. If set, the presenter view counts down to the end of it.
+    /// Default: None
+    pub talk_length: Option<Duration>,
+    /// Where to cache rendered LaTeX and dither maps between runs, or `None` to not cache
+    /// anything. Default: `~/.cache/ytesrev`, see [`default_cache_dir`]
+    ///
+    /// [`default_cache_dir`]: ../diskcache/fn.default_cache_dir.html
+    pub cache_dir: Option<PathBuf>,
+    /// The seed for ditherers that don't have a seed of their own, see [`Ditherer::seed`]. The
+    /// same seed makes every run dither the same way. Default: 0
+    ///
+    /// [`Ditherer::seed`]: ../ditherer/struct.Ditherer.html#structfield.seed
+    pub dither_seed: u64,
+    /// The programs used to render LaTeX, such as `xelatex` for system fonts. Default:
+    /// `pdflatex` and `pdftoppm`, see [`LatexBackend`]
+    ///
+    /// [`LatexBackend`]: ../latex/backend/struct.LatexBackend.html
+    pub latex_backend: LatexBackend,
 }
 
 /// Create default settings for a given title
@@ -72,6 +162,64 @@ pub fn default_settings(title: &str) -> WindowManagerSettings {
             } => true,
             _ => false,
         }),
+        event_step_back_rule: Box::new(|event| {
+            matches!(
+                event,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backspace),
+                    ..
+                }
+            )
+        }),
+        event_step_slide_back_rule: Box::new(|event| match event {
+            Event::KeyDown {
+                keycode: Some(Keycode::Backspace),
+                keymod,
+                ..
+            } => keymod.intersects(LSHIFTMOD | RSHIFTMOD),
+            _ => false,
+        }),
+        goto_input_rule: Box::new(|event| match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => match keycode {
+                Keycode::Num0 | Keycode::Kp0 => Some('0'),
+                Keycode::Num1 | Keycode::Kp1 => Some('1'),
+                Keycode::Num2 | Keycode::Kp2 => Some('2'),
+                Keycode::Num3 | Keycode::Kp3 => Some('3'),
+                Keycode::Num4 | Keycode::Kp4 => Some('4'),
+                Keycode::Num5 | Keycode::Kp5 => Some('5'),
+                Keycode::Num6 | Keycode::Kp6 => Some('6'),
+                Keycode::Num7 | Keycode::Kp7 => Some('7'),
+                Keycode::Num8 | Keycode::Kp8 => Some('8'),
+                Keycode::Num9 | Keycode::Kp9 => Some('9'),
+                Keycode::Period | Keycode::KpPeriod => Some('.'),
+                _ => None,
+            },
+            _ => None,
+        }),
+        goto_rule: Box::new(|event| {
+            matches!(
+                event,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Return),
+                    ..
+                } | Event::KeyDown {
+                    keycode: Some(Keycode::KpEnter),
+                    ..
+                }
+            )
+        }),
+        overview_rule: Box::new(|event| {
+            matches!(
+                event,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Tab),
+                    ..
+                }
+            )
+        }),
         quit_rule: Box::new(|event| match event {
             Event::KeyDown {
                 keycode: Some(Keycode::Escape),
@@ -80,6 +228,10 @@ pub fn default_settings(title: &str) -> WindowManagerSettings {
             Event::Quit { .. } => true,
             _ => false,
         }),
+        talk_length: None,
+        cache_dir: default_cache_dir(),
+        dither_seed: 0,
+        latex_backend: LatexBackend::default(),
     }
 }
 
@@ -87,26 +239,56 @@ pub fn default_settings(title: &str) -> WindowManagerSettings {
 pub const WSETTINGS_MAIN: WindowSettings = WindowSettings {
     draw_settings: DSETTINGS_MAIN,
     window_size: (1200, 800),
+    display: 0,
+    fullscreen: FullscreenType::Off,
+    borderless: false,
+    position: None,
 };
 
 /// The default window settings for the notes window
 pub const WSETTINGS_NOTES: WindowSettings = WindowSettings {
     draw_settings: DSETTINGS_NOTES,
     window_size: (600, 400),
+    ..WSETTINGS_MAIN
 };
 
+/// Create settings for presenting with a projector. The main window is opened in fullscreen on
+/// the projector's display, and the notes window in fullscreen on the first display, usually the
+/// one on the laptop.
+pub fn projector_settings(title: &str, projector_display: i32) -> WindowManagerSettings {
+    let mut settings = default_settings(title);
+    for (i, (_, wsettings)) in settings.windows.iter_mut().enumerate() {
+        wsettings.fullscreen = FullscreenType::Desktop;
+        if i == 0 {
+            wsettings.display = projector_display;
+        }
+    }
+    settings
+}
+
 /// The manager of the entire presentation.
 pub struct WindowManager<T: Scene> {
     /// The sdl context from `sdl2::init`
     pub context: Sdl,
 
-    /// All canvases, together with their respective settings
-    pub canvases: Vec<(WindowSettings, Canvas<Window>)>,
+    /// All canvases, together with their respective settings. The canvases keep the textures of
+    /// the images drawn to them between frames.
+    pub canvases: Vec<(WindowSettings, CachedCanvas<Window>)>,
 
     /// What events should make the presentation step forward?
     pub event_step_rule: Box<dyn Fn(Event) -> bool>,
     /// What events should make the presentation step one slide forward?
     pub event_step_slide_rule: Box<dyn Fn(Event) -> bool>,
+    /// What events should undo the last step?
+    pub event_step_back_rule: Box<dyn Fn(Event) -> bool>,
+    /// What events should make the presentation go back one slide?
+    pub event_step_slide_back_rule: Box<dyn Fn(Event) -> bool>,
+    /// What character, if any, does an event type when entering a slide to go to?
+    pub goto_input_rule: Box<dyn Fn(Event) -> Option<char>>,
+    /// What events should go to the entered slide?
+    pub goto_rule: Box<dyn Fn(Event) -> bool>,
+    /// What events should show or hide the overview of all slides?
+    pub overview_rule: Box<dyn Fn(Event) -> bool>,
     /// At what event should the presentation quit? Default: On escape or the window is closed.
     pub quit_rule: Box<dyn Fn(Event) -> bool>,
 
@@ -116,8 +298,19 @@ pub struct WindowManager<T: Scene> {
     /// The scene being presented
     pub scene: T,
 
+    /// Another instance of the scene, kept one step ahead of it to preview the next step in the
+    /// presenter view. See [`WindowManager::with_preview`].
+    ///
+    /// [`WindowManager::with_preview`]: #method.with_preview
+    pub preview: Option<T>,
+
+    /// How long the talk has been going on, shown in the presenter view
+    pub timer: TalkTimer,
+
     time_manager: Option<TimeManager>,
     tick: usize,
+    goto_input: String,
+    goto_typed: Instant,
 }
 
 struct TimeManager {
@@ -131,35 +324,56 @@ impl<T: Scene> WindowManager<T> {
     ///
     /// This loads all scences and creates the windows according to the settings
     pub fn init_window(mut scene: T, settings: WindowManagerSettings) -> WindowManager<T> {
-        // Load everything
-        scene.register();
-
-        let start = Instant::now();
-        eprintln!("Rendering LaTeX...");
-        render_all_equations().expect("Can't render!");
-
-        eprintln!("Loading scenes");
-        scene.load();
-
-        let delta = Instant::now() - start;
-        eprintln!(
-            "Done! Took {:.2}s",
-            delta.as_secs() as f64 + delta.subsec_millis() as f64 / 1000.
-        );
+        set_cache_dir(settings.cache_dir.clone());
+        set_default_seed(settings.dither_seed);
+        set_backend(settings.latex_backend.clone());
+        load_scene(&mut scene);
 
         let mut canvases = Vec::with_capacity(settings.windows.len());
 
         let sdl_context = sdl2::init().unwrap();
         let video_subsystem = sdl_context.video().unwrap();
 
+        // Smooth out anything that's scaled, such as by the logical size or a `ScaleToFit`
+        sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "1");
+
         for (title, settings) in settings.windows {
-            let window = video_subsystem
-                .window(&title, settings.window_size.0, settings.window_size.1)
-                .position_centered()
-                .resizable()
-                .build()
-                .unwrap();
-            let canvas = window.into_canvas().build().unwrap();
+            let ndisplays = video_subsystem.num_video_displays().unwrap_or(1);
+            let display = if (0..ndisplays).contains(&settings.display) {
+                settings.display
+            } else {
+                eprintln!(
+                    "No display {} for window {}, using the first one",
+                    settings.display, title
+                );
+                0
+            };
+            let bounds = video_subsystem
+                .display_bounds(display)
+                .expect("Can't get display bounds");
+
+            let (width, height) = settings.window_size;
+            let (x, y) = settings.position.unwrap_or((
+                (bounds.width() as i32 - width as i32) / 2,
+                (bounds.height() as i32 - height as i32) / 2,
+            ));
+
+            let mut builder = video_subsystem.window(&title, width, height);
+            builder.position(bounds.x() + x, bounds.y() + y).resizable();
+            match settings.fullscreen {
+                FullscreenType::Off => {}
+                FullscreenType::True => {
+                    builder.fullscreen();
+                }
+                FullscreenType::Desktop => {
+                    builder.fullscreen_desktop();
+                }
+            }
+            if settings.borderless {
+                builder.borderless();
+            }
+            let window = builder.build().unwrap();
+            let canvas = CachedCanvas::new(window.into_canvas().build().unwrap());
 
             canvases.push((settings, canvas));
         }
@@ -171,19 +385,50 @@ impl<T: Scene> WindowManager<T> {
             canvases,
             event_step_rule: settings.event_step_rule,
             event_step_slide_rule: settings.event_step_slide_rule,
+            event_step_back_rule: settings.event_step_back_rule,
+            event_step_slide_back_rule: settings.event_step_slide_back_rule,
+            goto_input_rule: settings.goto_input_rule,
+            goto_rule: settings.goto_rule,
+            overview_rule: settings.overview_rule,
             quit_rule: settings.quit_rule,
             event_pump,
             scene,
+            preview: None,
+            timer: TalkTimer::new(settings.talk_length),
             time_manager: None,
             tick: 0,
+            goto_input: String::new(),
+            goto_typed: Instant::now(),
         }
     }
 
+    /// Show a preview of the next step in the presenter view. The preview has to be another
+    /// instance of the same scene, it's loaded and then kept one step ahead of the scene being
+    /// presented. Only the preview has its animations skipped, the scene being presented is never
+    /// stepped to draw it.
+    ///
+    /// The LaTeX and dither maps of the preview are the same as those of the scene, so they're
+    /// loaded from the cache instead of being rendered again, unless the cache is turned off, see
+    /// [`WindowManagerSettings::cache_dir`].
+    ///
+    /// [`WindowManagerSettings::cache_dir`]: struct.WindowManagerSettings.html#structfield.cache_dir
+    pub fn with_preview(mut self, mut preview: T) -> WindowManager<T> {
+        load_scene(&mut preview);
+        preview.event(YEvent::Step);
+        self.preview = Some(preview);
+        self.sync_preview();
+        self
+    }
+
     fn process_events(&mut self) -> bool {
         if let Some(ref mut tm) = self.time_manager {
             let dt = tm.dt();
 
             self.scene.update(dt);
+            if let Some(ref mut preview) = self.preview {
+                preview.update(dt);
+                preview.skip_animation();
+            }
             match self.scene.action() {
                 Action::Done => {
                     return false;
@@ -191,13 +436,42 @@ impl<T: Scene> WindowManager<T> {
                 _ => {}
             }
 
-            for event in self.event_pump.poll_iter() {
+            if self.goto_typed.elapsed() > GOTO_TIMEOUT {
+                self.goto_input.clear();
+            }
+
+            let events: Vec<Event> = self.event_pump.poll_iter().collect();
+            for event in events {
+                let goto_char = (*self.goto_input_rule)(event.clone());
+                // Anything else typed or clicked abandons the slide being entered
+                if goto_char.is_none() && !(*self.goto_rule)(event.clone()) && is_input(&event) {
+                    self.goto_input.clear();
+                }
+
                 if (*self.quit_rule)(event.clone()) {
                     return false;
+                } else if let Some(ch) = goto_char {
+                    self.goto_input.push(ch);
+                    self.goto_typed = Instant::now();
+                } else if !self.goto_input.is_empty() && (*self.goto_rule)(event.clone()) {
+                    if let Some((scene, step)) = parse_goto(&self.goto_input) {
+                        self.send_event(YEvent::Goto(scene, step));
+                    }
+                    self.goto_input.clear();
+                } else if (*self.overview_rule)(event.clone()) {
+                    self.scene.event(YEvent::ToggleOverview);
+                } else if let Some(click) =
+                    overview_click(self.scene.is_overview_shown(), &self.canvases, &event)
+                {
+                    self.send_event(click);
+                } else if (*self.event_step_slide_back_rule)(event.clone()) {
+                    self.send_event(YEvent::StepSlideBack)
                 } else if (*self.event_step_slide_rule)(event.clone()) {
-                    self.scene.event(YEvent::StepSlide)
+                    self.send_event(YEvent::StepSlide)
+                } else if (*self.event_step_back_rule)(event.clone()) {
+                    self.send_event(YEvent::StepBack)
                 } else if (*self.event_step_rule)(event.clone()) {
-                    self.scene.event(YEvent::Step)
+                    self.send_event(YEvent::Step)
                 } else {
                     self.scene.event(YEvent::Other(event))
                 }
@@ -209,19 +483,47 @@ impl<T: Scene> WindowManager<T> {
         true
     }
 
+    /// Send an event that moves the presentation to the scene, and start the talk timer
+    fn send_event(&mut self, event: YEvent) {
+        self.timer.start();
+
+        if let Some(ref mut preview) = self.preview {
+            preview.event(event.clone());
+        }
+        self.scene.event(event);
+
+        self.sync_preview();
+    }
+
+    /// Move the preview to the step after the one being shown. Scenes that can't jump to a step,
+    /// such as a [`DrawableWrapper`], stay in sync by getting the same events as the scene.
+    ///
+    /// [`DrawableWrapper`]: ../scene/struct.DrawableWrapper.html
+    fn sync_preview(&mut self) {
+        if let Some(ref mut preview) = self.preview {
+            let (slide, step) = (self.scene.slide(), self.scene.slide_step());
+            preview.event(YEvent::Goto(slide, step + 1));
+            preview.skip_animation();
+        }
+    }
+
     fn draw(&mut self) {
         for (ref mut settings, ref mut canvas) in &mut self.canvases {
-            canvas.set_draw_color(Color::RGBA(
-                settings.draw_settings.background_color.0,
-                settings.draw_settings.background_color.1,
-                settings.draw_settings.background_color.2,
-                255,
-            ));
-            canvas.clear();
-
             settings.window_size = canvas.window().size();
 
-            self.scene.draw(canvas, settings.draw_settings);
+            // The overview needs the entire window
+            if settings.draw_settings.notes_view && !self.scene.is_overview_shown() {
+                draw_presenter(
+                    &self.scene,
+                    self.preview.as_ref(),
+                    &self.timer,
+                    &self.goto_input,
+                    canvas,
+                    settings.draw_settings,
+                );
+            } else {
+                draw_scene(&self.scene, canvas, settings.draw_settings);
+            }
 
             canvas.present();
         }
@@ -240,6 +542,204 @@ impl<T: Scene> WindowManager<T> {
             sleep(Duration::from_millis(5));
         }
     }
+
+    /// Exports the final frame of every step in the presentation as numbered PNG files instead of
+    /// presenting it, using the size and draw settings of the first window. See
+    /// [`export_steps`].
+    ///
+    /// [`export_steps`]: ../export/fn.export_steps.html
+    pub fn export_steps(&mut self, dir: &Path) -> IResult<usize> {
+        let settings = self.export_settings();
+        export_steps(&mut self.scene, &settings, dir)
+    }
+
+    /// Exports the presentation as a PDF handout instead of presenting it, with one page for
+    /// every step and the speaker notes under each page. See [`export_handout`].
+    ///
+    /// [`export_handout`]: ../export/handout/fn.export_handout.html
+    pub fn export_handout(&mut self, path: &Path) -> IResult<usize> {
+        let settings = HandoutSettings {
+            export: self.export_settings(),
+            ..HSETTINGS_MAIN
+        };
+        export_handout(&mut self.scene, &settings, path)
+    }
+
+    /// Records the presentation instead of presenting it. The presentation is advanced with a
+    /// fixed timestep and stepped at the times in the settings, independent of how long each
+    /// frame takes to draw. The windows show the recording as it's made, and the recording can
+    /// be stopped using the quit rule. See [`record_with`].
+    ///
+    /// [`record_with`]: ../export/video/fn.record_with.html
+    pub fn record(&mut self, settings: &RecordSettings) -> IResult<usize> {
+        let canvases = &mut self.canvases;
+        let event_pump = &mut self.event_pump;
+        let quit_rule = &self.quit_rule;
+
+        record_with(&mut self.scene, settings, |scene| {
+            for (ref mut wsettings, ref mut canvas) in canvases.iter_mut() {
+                wsettings.window_size = canvas.window().size();
+                draw_scene(scene, canvas, wsettings.draw_settings);
+                canvas.present();
+            }
+
+            !event_pump.poll_iter().any(|event| (*quit_rule)(event))
+        })
+    }
+
+    fn export_settings(&self) -> ExportSettings {
+        match self.canvases.first() {
+            Some((wsettings, _)) => ExportSettings {
+                size: wsettings.window_size,
+                draw_settings: wsettings.draw_settings,
+                ..ESETTINGS_MAIN
+            },
+            None => ESETTINGS_MAIN,
+        }
+    }
+}
+
+/// Register and load a scene, and render all LaTeX in it. This is done automatically by
+/// [`WindowManager::init_window`], but is needed when drawing a scene without a window, such as
+/// into an [`offscreen_canvas`].
+///
+/// [`WindowManager::init_window`]: struct.WindowManager.html#method.init_window
+/// [`offscreen_canvas`]: ../drawable/fn.offscreen_canvas.html
+pub fn load_scene<T: Scene>(scene: &mut T) {
+    scene.register();
+
+    let start = Instant::now();
+    eprintln!("Rendering LaTeX...");
+    if let Err(e) = render_all_equations() {
+        eprintln!("Couldn't render LaTeX: {}", e);
+    }
+
+    eprintln!("Loading scenes");
+    scene.load();
+
+    let delta = Instant::now() - start;
+    eprintln!(
+        "Done! Took {:.2}s",
+        delta.as_secs() as f64 + delta.subsec_millis() as f64 / 1000.
+    );
+}
+
+/// Draw a single frame of a scene, filling the target with the background color first. If the
+/// settings have a logical size, the scene is scaled to fit the target, with black bars around
+/// it. The frame isn't presented, that's up to the owner of the target.
+pub fn draw_scene<T: Scene + ?Sized>(
+    scene: &T,
+    canvas: &mut dyn DrawTarget,
+    settings: DrawSettings,
+) {
+    let viewport = canvas.viewport();
+    let size = (viewport.width(), viewport.height());
+
+    match settings.logical_size {
+        Some(logical_size) if logical_size != size => {
+            canvas.set_draw_color(LETTERBOX_COLOR);
+            canvas
+                .fill_rect(Rect::new(0, 0, size.0, size.1))
+                .expect("Can't draw");
+
+            let (scale, rect) = fit_into(logical_size, Rect::new(0, 0, size.0, size.1));
+            if scale > 0. {
+                draw_scaled(canvas, rect, scale, |canvas| {
+                    draw_background(canvas, settings);
+                    scene.draw(canvas, settings);
+                });
+            }
+        }
+        _ => {
+            draw_background(canvas, settings);
+            scene.draw(canvas, settings);
+        }
+    }
+}
+
+fn draw_background(canvas: &mut dyn DrawTarget, settings: DrawSettings) {
+    let viewport = canvas.viewport();
+    canvas.set_draw_color(Color::RGBA(
+        settings.background_color.0,
+        settings.background_color.1,
+        settings.background_color.2,
+        255,
+    ));
+    canvas
+        .fill_rect(Rect::new(0, 0, viewport.width(), viewport.height()))
+        .expect("Can't draw");
+}
+
+/// Convert a point in a window to the logical coordinates the scene is drawn in, see
+/// [`DrawSettings::logical_size`]. Returns the point and the logical size.
+///
+/// [`DrawSettings::logical_size`]: ../drawable/struct.DrawSettings.html#structfield.logical_size
+pub fn to_logical(
+    point: (i32, i32),
+    size: (u32, u32),
+    settings: DrawSettings,
+) -> ((i32, i32), (u32, u32)) {
+    match settings.logical_size {
+        Some(logical_size) if logical_size != size => {
+            let (scale, rect) = fit_into(logical_size, Rect::new(0, 0, size.0, size.1));
+            let x = ((point.0 - rect.x()) as f64 / scale) as i32;
+            let y = ((point.1 - rect.y()) as f64 / scale) as i32;
+            ((x, y), logical_size)
+        }
+        _ => (point, size),
+    }
+}
+
+/// Turn a mouse press in a notes window into an `OverviewClick`, if the overview is shown
+fn overview_click(
+    overview: bool,
+    canvases: &[(WindowSettings, CachedCanvas<Window>)],
+    event: &Event,
+) -> Option<YEvent> {
+    if !overview {
+        return None;
+    }
+
+    match *event {
+        Event::MouseButtonDown {
+            window_id, x, y, ..
+        } => {
+            let (settings, canvas) = canvases.iter().find(|(settings, canvas)| {
+                settings.draw_settings.notes_view && canvas.window().id() == window_id
+            })?;
+
+            // The mouse position is in window coordinates, which aren't always pixels
+            let (width, height) = canvas.window().size();
+            let size = canvas.output_size().ok()?;
+            let pixel = (
+                (x as i64 * size.0 as i64 / width.max(1) as i64) as i32,
+                (y as i64 * size.1 as i64 / height.max(1) as i64) as i32,
+            );
+
+            let ((x, y), size) = to_logical(pixel, size, settings.draw_settings);
+            Some(YEvent::OverviewClick { x, y, size })
+        }
+        _ => None,
+    }
+}
+
+/// Parse a slide to go to, formatted as `slide` or `slide.step`, into zero-based indices
+/// Is the event a key or button being pressed?
+fn is_input(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::KeyDown { .. } | Event::MouseButtonDown { .. } | Event::FingerDown { .. }
+    )
+}
+
+fn parse_goto(input: &str) -> Option<(usize, usize)> {
+    let mut parts = input.splitn(2, '.');
+    let scene = parts.next()?.parse::<usize>().ok()?.checked_sub(1)?;
+    let step = match parts.next() {
+        Some(step) if !step.is_empty() => step.parse().ok()?,
+        _ => 0,
+    };
+    Some((scene, step))
 }
 
 impl TimeManager {
@@ -273,3 +773,60 @@ impl TimeManager {
         diff.as_secs() as f64 + diff.subsec_millis() as f64 / 1000.
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goto_counts_slides_from_one() {
+        assert_eq!(parse_goto("3"), Some((2, 0)));
+        assert_eq!(parse_goto("3."), Some((2, 0)));
+        assert_eq!(parse_goto("3.4"), Some((2, 4)));
+        assert_eq!(parse_goto("12.0"), Some((11, 0)));
+    }
+
+    #[test]
+    fn goto_rejects_invalid_input() {
+        assert_eq!(parse_goto(""), None);
+        assert_eq!(parse_goto("0"), None);
+        assert_eq!(parse_goto("x"), None);
+        assert_eq!(parse_goto("2.x"), None);
+        assert_eq!(parse_goto(".2"), None);
+    }
+
+    #[test]
+    fn clicks_are_moved_into_the_logical_size() {
+        let settings = DrawSettings {
+            logical_size: Some((200, 100)),
+            ..DSETTINGS_MAIN
+        };
+
+        // Scaled by 2, with bars of 100 pixels above and below
+        assert_eq!(
+            to_logical((200, 200), (400, 400), settings),
+            ((100, 50), (200, 100))
+        );
+        assert_eq!(
+            to_logical((0, 100), (400, 400), settings),
+            ((0, 0), (200, 100))
+        );
+        assert_eq!(
+            to_logical((30, 40), (200, 100), settings),
+            ((30, 40), (200, 100))
+        );
+    }
+
+    #[test]
+    fn clicks_without_a_logical_size_are_unchanged() {
+        let settings = DrawSettings {
+            logical_size: None,
+            ..DSETTINGS_MAIN
+        };
+
+        assert_eq!(
+            to_logical((30, 40), (400, 400), settings),
+            ((30, 40), (400, 400))
+        );
+    }
+}
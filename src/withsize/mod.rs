@@ -1,9 +1,6 @@
 //! Make an object have a static size
 
-use sdl2::render::Canvas;
-use sdl2::video::Window;
-
-use drawable::{DrawSettings, Drawable, KnownSize, Position, State};
+use drawable::{DrawSettings, DrawTarget, Drawable, KnownSize, Position, State};
 
 /// A wrapper that makes an object have a known size
 pub struct WithSize<T: Drawable> {
@@ -41,11 +38,15 @@ impl<T: Drawable> Drawable for WithSize<T> {
         self.inner.step()
     }
 
+    fn step_back(&mut self) {
+        self.inner.step_back()
+    }
+
     fn state(&self) -> State {
         self.inner.state()
     }
 
-    fn draw(&self, canvas: &mut Canvas<Window>, pos: &Position, settings: DrawSettings) {
+    fn draw(&self, canvas: &mut dyn DrawTarget, pos: &Position, settings: DrawSettings) {
         self.inner.draw(canvas, pos, settings);
     }
 }